@@ -0,0 +1,318 @@
+//! Parsing of the `#[json(...)]` attributes
+//!
+//! Container attributes: `rename_all = "..."`, `deny_unknown_fields`,
+//! `tag = "..."`, `content = "..."`, `untagged`.
+//! Variant attributes: `rename = "..."`, `rename_all = "..."`.
+//! Field attributes: `rename = "..."`, `default`, `default = "path"`, `skip`,
+//! `skip_serializing_if = "path"`, `flatten`.
+
+use proc_macro2::Span;
+use syn::ext::IdentExt;
+use syn::meta::ParseNestedMeta;
+use syn::{Attribute, ExprPath, Ident, LitStr};
+
+/// Attributes set on the struct or enum itself
+#[derive(Default)]
+pub struct Container {
+    pub rename_all: Option<RenameRule>,
+    pub deny_unknown_fields: bool,
+    pub representation: Representation,
+}
+
+/// How the variants of an enum are laid out in JSON
+#[derive(Default)]
+pub enum Representation {
+    /// `{"Variant": content}`, or `"Variant"` for unit variants
+    #[default]
+    External,
+    /// `{"tag": "Variant", ...fields}`
+    Internal { tag: String },
+    /// `{"tag": "Variant", "content": content}`
+    Adjacent { tag: String, content: String },
+    /// The content alone
+    Untagged,
+}
+
+impl Container {
+    pub fn from_attributes(attributes: &[Attribute]) -> syn::Result<Self> {
+        let mut container = Container::default();
+        let mut tag = None;
+        let mut content = None;
+        let mut untagged = false;
+        parse_json_attributes(attributes, |meta| {
+            if meta.path.is_ident("rename_all") {
+                container.rename_all = Some(RenameRule::parse(&meta)?);
+            } else if meta.path.is_ident("deny_unknown_fields") {
+                container.deny_unknown_fields = true;
+            } else if meta.path.is_ident("tag") {
+                tag = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("content") {
+                content = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("untagged") {
+                untagged = true;
+            } else {
+                return Err(meta.error("unknown json container attribute"));
+            }
+            Ok(())
+        })?;
+
+        container.representation = match (tag, content, untagged) {
+            (None, None, false) => Representation::External,
+            (Some(tag), None, false) => Representation::Internal { tag },
+            (Some(tag), Some(content), false) => Representation::Adjacent { tag, content },
+            (None, None, true) => Representation::Untagged,
+            (None, Some(_), false) => {
+                return Err(syn::Error::new(
+                    Span::call_site(),
+                    "`content` requires `tag` to be set",
+                ));
+            }
+            (_, _, true) => {
+                return Err(syn::Error::new(
+                    Span::call_site(),
+                    "`untagged` cannot be combined with `tag` or `content`",
+                ));
+            }
+        };
+        Ok(container)
+    }
+
+    /// Rejects the enum representation attributes on a struct
+    pub fn check_struct(&self, ident: &Ident) -> syn::Result<()> {
+        match self.representation {
+            Representation::External => Ok(()),
+            _ => Err(syn::Error::new_spanned(
+                ident,
+                "`tag`, `content` and `untagged` only apply to enums",
+            )),
+        }
+    }
+}
+
+/// Attributes set on an enum variant
+#[derive(Default)]
+pub struct Variant {
+    pub rename: Option<String>,
+    pub rename_all: Option<RenameRule>,
+}
+
+impl Variant {
+    pub fn from_attributes(attributes: &[Attribute]) -> syn::Result<Self> {
+        let mut variant = Variant::default();
+        parse_json_attributes(attributes, |meta| {
+            if meta.path.is_ident("rename") {
+                variant.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("rename_all") {
+                variant.rename_all = Some(RenameRule::parse(&meta)?);
+            } else {
+                return Err(meta.error("unknown json variant attribute"));
+            }
+            Ok(())
+        })?;
+        Ok(variant)
+    }
+
+    /// Name of the variant in JSON
+    pub fn tag(&self, ident: &Ident, rename_all: Option<RenameRule>) -> String {
+        let name = ident.unraw().to_string();
+        match (&self.rename, rename_all) {
+            (Some(rename), _) => rename.clone(),
+            (None, Some(rule)) => rule.apply_to_variant(&name),
+            (None, None) => name,
+        }
+    }
+}
+
+/// Attributes set on a struct or variant field
+#[derive(Default)]
+pub struct Field {
+    pub rename: Option<String>,
+    pub default: Option<DefaultValue>,
+    pub skip: bool,
+    pub skip_serializing_if: Option<ExprPath>,
+    pub flatten: bool,
+}
+
+/// Where the value of a missing field comes from
+pub enum DefaultValue {
+    Trait,
+    Path(ExprPath),
+}
+
+impl Field {
+    pub fn from_attributes(attributes: &[Attribute]) -> syn::Result<Self> {
+        let mut field = Field::default();
+        parse_json_attributes(attributes, |meta| {
+            if meta.path.is_ident("rename") {
+                field.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("default") {
+                field.default = if meta.input.peek(syn::Token![=]) {
+                    Some(DefaultValue::Path(parse_path(&meta)?))
+                } else {
+                    Some(DefaultValue::Trait)
+                };
+            } else if meta.path.is_ident("skip") {
+                field.skip = true;
+            } else if meta.path.is_ident("skip_serializing_if") {
+                field.skip_serializing_if = Some(parse_path(&meta)?);
+            } else if meta.path.is_ident("flatten") {
+                field.flatten = true;
+            } else {
+                return Err(meta.error("unknown json field attribute"));
+            }
+            Ok(())
+        })?;
+        Ok(field)
+    }
+
+    /// Key of the field in JSON
+    pub fn key(&self, ident: &Ident, rename_all: Option<RenameRule>) -> String {
+        let name = ident.unraw().to_string();
+        match (&self.rename, rename_all) {
+            (Some(rename), _) => rename.clone(),
+            (None, Some(rule)) => rule.apply_to_field(&name),
+            (None, None) => name,
+        }
+    }
+}
+
+/// Case conversion applied by `rename_all`
+#[derive(Clone, Copy)]
+pub enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn parse(meta: &ParseNestedMeta) -> syn::Result<Self> {
+        let rule = meta.value()?.parse::<LitStr>()?;
+        match rule.value().as_str() {
+            "lowercase" => Ok(RenameRule::Lower),
+            "UPPERCASE" => Ok(RenameRule::Upper),
+            "PascalCase" => Ok(RenameRule::Pascal),
+            "camelCase" => Ok(RenameRule::Camel),
+            "snake_case" => Ok(RenameRule::Snake),
+            "SCREAMING_SNAKE_CASE" => Ok(RenameRule::ScreamingSnake),
+            "kebab-case" => Ok(RenameRule::Kebab),
+            "SCREAMING-KEBAB-CASE" => Ok(RenameRule::ScreamingKebab),
+            _ => Err(syn::Error::new_spanned(
+                rule,
+                "unknown rename rule, expected one of \"lowercase\", \"UPPERCASE\", \
+                 \"PascalCase\", \"camelCase\", \"snake_case\", \"SCREAMING_SNAKE_CASE\", \
+                 \"kebab-case\", \"SCREAMING-KEBAB-CASE\"",
+            )),
+        }
+    }
+
+    /// Renames a field, written in snake_case in Rust
+    pub fn apply_to_field(self, field: &str) -> String {
+        let words: Vec<&str> = field.split('_').filter(|word| !word.is_empty()).collect();
+        self.join(&words)
+    }
+
+    /// Renames a variant, written in PascalCase in Rust
+    pub fn apply_to_variant(self, variant: &str) -> String {
+        let mut words = Vec::new();
+        let mut start = 0;
+        for (index, c) in variant.char_indices().skip(1) {
+            if c.is_uppercase() {
+                words.push(&variant[start..index]);
+                start = index;
+            }
+        }
+        words.push(&variant[start..]);
+        self.join(&words)
+    }
+
+    fn join(self, words: &[&str]) -> String {
+        let lower: Vec<String> = words.iter().map(|word| word.to_lowercase()).collect();
+        let upper: Vec<String> = words.iter().map(|word| word.to_uppercase()).collect();
+        match self {
+            RenameRule::Lower => lower.concat(),
+            RenameRule::Upper => upper.concat(),
+            RenameRule::Pascal => lower.iter().map(|word| capitalize(word)).collect(),
+            RenameRule::Camel => lower
+                .iter()
+                .enumerate()
+                .map(|(index, word)| match index {
+                    0 => word.clone(),
+                    _ => capitalize(word),
+                })
+                .collect(),
+            RenameRule::Snake => lower.join("_"),
+            RenameRule::ScreamingSnake => upper.join("_"),
+            RenameRule::Kebab => lower.join("-"),
+            RenameRule::ScreamingKebab => upper.join("-"),
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Paths are written as strings, as in `skip_serializing_if = "Option::is_none"`
+fn parse_path(meta: &ParseNestedMeta) -> syn::Result<ExprPath> {
+    meta.value()?.parse::<LitStr>()?.parse()
+}
+
+fn parse_json_attributes(
+    attributes: &[Attribute],
+    mut parse: impl FnMut(ParseNestedMeta) -> syn::Result<()>,
+) -> syn::Result<()> {
+    for attribute in attributes {
+        if attribute.path().is_ident("json") {
+            attribute.parse_nested_meta(&mut parse)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_rename_fields() {
+        let field = "user_id_list";
+
+        assert_eq!("useridlist", RenameRule::Lower.apply_to_field(field));
+        assert_eq!("USERIDLIST", RenameRule::Upper.apply_to_field(field));
+        assert_eq!("UserIdList", RenameRule::Pascal.apply_to_field(field));
+        assert_eq!("userIdList", RenameRule::Camel.apply_to_field(field));
+        assert_eq!("user_id_list", RenameRule::Snake.apply_to_field(field));
+        assert_eq!(
+            "USER_ID_LIST",
+            RenameRule::ScreamingSnake.apply_to_field(field)
+        );
+        assert_eq!("user-id-list", RenameRule::Kebab.apply_to_field(field));
+        assert_eq!(
+            "USER-ID-LIST",
+            RenameRule::ScreamingKebab.apply_to_field(field)
+        );
+    }
+
+    #[test]
+    fn it_should_rename_variants() {
+        let variant = "UserIdList";
+
+        assert_eq!("useridlist", RenameRule::Lower.apply_to_variant(variant));
+        assert_eq!("UserIdList", RenameRule::Pascal.apply_to_variant(variant));
+        assert_eq!("userIdList", RenameRule::Camel.apply_to_variant(variant));
+        assert_eq!("user_id_list", RenameRule::Snake.apply_to_variant(variant));
+        assert_eq!(
+            "USER-ID-LIST",
+            RenameRule::ScreamingKebab.apply_to_variant(variant)
+        );
+    }
+}
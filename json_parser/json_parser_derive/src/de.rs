@@ -0,0 +1,448 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{Data, DataEnum, DeriveInput, Fields, parse_quote};
+
+use crate::attr::{self, DefaultValue, RenameRule, Representation};
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let type_name = name.unraw().to_string();
+    let container = attr::Container::from_attributes(&input.attrs)?;
+    let json = quote!(__json);
+    let body = match &input.data {
+        Data::Struct(data) => {
+            container.check_struct(name)?;
+            let target = Target {
+                constructor: quote!(Self),
+                type_name,
+                fields: &data.fields,
+                rename_all: container.rename_all,
+                deny_unknown_fields: container.deny_unknown_fields,
+            };
+            deserialize_fields(&target, &json)?
+        }
+        Data::Enum(data) => deserialize_enum(&type_name, data, &container, &json)?,
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "Deserialize cannot be derived for unions",
+            ));
+        }
+    };
+
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::json_parser::Deserialize));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::json_parser::Deserialize for #name #ty_generics #where_clause {
+            fn deserialize(
+                #json: ::json_parser::JsonType,
+            ) -> ::std::result::Result<Self, ::json_parser::error::JsonError> {
+                #body
+            }
+        }
+    })
+}
+
+/// A struct or an enum variant to build out of a `JsonType`
+struct Target<'a> {
+    /// Path used to build the value, `Self` or `Self::Variant`
+    constructor: TokenStream,
+    /// Name used in error messages
+    type_name: String,
+    fields: &'a Fields,
+    rename_all: Option<RenameRule>,
+    deny_unknown_fields: bool,
+}
+
+/// Builds the target out of the `JsonType` held by the `json` variable
+///
+/// The generated expression evaluates to a `Result<Self, JsonError>`.
+fn deserialize_fields(target: &Target, json: &TokenStream) -> syn::Result<TokenStream> {
+    let Target {
+        constructor,
+        type_name,
+        fields,
+        ..
+    } = target;
+    match fields {
+        Fields::Named(_) => deserialize_named_fields(target, json),
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => Ok(quote! {
+            ::std::result::Result::Ok(#constructor(
+                ::json_parser::Deserialize::deserialize(#json)?,
+            ))
+        }),
+        Fields::Unnamed(unnamed) => {
+            let mut elements = Vec::new();
+            let mut values = Vec::new();
+            for (index, field) in unnamed.unnamed.iter().enumerate() {
+                let attrs = attr::Field::from_attributes(&field.attrs)?;
+                if attrs.skip {
+                    values.push(default_value(&attrs.default));
+                } else {
+                    let element = format_ident!("__field{}", index);
+                    values.push(quote!(::json_parser::Deserialize::deserialize(#element)?));
+                    elements.push(element);
+                }
+            }
+            let length = elements.len();
+
+            Ok(quote! {{
+                let __array = match #json {
+                    ::json_parser::JsonType::Array(__array) => __array,
+                    __other => {
+                        return ::std::result::Result::Err(
+                            ::json_parser::private::invalid_type("an array", #type_name, &__other),
+                        );
+                    }
+                };
+                let [#(#elements),*] = match <[::json_parser::JsonType; #length]>::try_from(__array) {
+                    ::std::result::Result::Ok(__elements) => __elements,
+                    ::std::result::Result::Err(__array) => {
+                        return ::std::result::Result::Err(
+                            ::json_parser::private::invalid_length(#length, __array.len(), #type_name),
+                        );
+                    }
+                };
+                ::std::result::Result::Ok(#constructor(#(#values),*))
+            }})
+        }
+        Fields::Unit => Ok(quote! {
+            match #json {
+                ::json_parser::JsonType::Null => ::std::result::Result::Ok(#constructor),
+                __other => ::std::result::Result::Err(
+                    ::json_parser::private::invalid_type("null", #type_name, &__other),
+                ),
+            }
+        }),
+    }
+}
+
+/// Reads the members of an object into the named fields of the target
+///
+/// Members matching no field are ignored, rejected with `deny_unknown_fields`
+/// or collected for the `flatten` fields.
+fn deserialize_named_fields(target: &Target, json: &TokenStream) -> syn::Result<TokenStream> {
+    let Target {
+        constructor,
+        type_name,
+        fields,
+        rename_all,
+        deny_unknown_fields,
+    } = target;
+
+    let mut slots = Vec::new();
+    let mut arms = Vec::new();
+    let mut members = Vec::new();
+    let mut has_flatten = false;
+    for (index, field) in fields.iter().enumerate() {
+        let attrs = attr::Field::from_attributes(&field.attrs)?;
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let slot = format_ident!("__field{}", index);
+        let ty = &field.ty;
+        let key = attrs.key(ident, *rename_all);
+
+        let value = if attrs.skip {
+            default_value(&attrs.default)
+        } else if attrs.flatten {
+            if *deny_unknown_fields {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "flatten cannot be combined with deny_unknown_fields",
+                ));
+            }
+            has_flatten = true;
+            quote! {
+                ::json_parser::Deserialize::deserialize(::json_parser::JsonType::Object(
+                    ::std::boxed::Box::new(::json_parser::Object::new(__flattened.clone())),
+                ))?
+            }
+        } else {
+            slots.push(quote! {
+                let mut #slot: ::std::option::Option<#ty> = ::std::option::Option::None;
+            });
+            arms.push(quote! {
+                #key => {
+                    #slot = ::std::option::Option::Some(
+                        ::json_parser::Deserialize::deserialize(__value)?,
+                    );
+                }
+            });
+            let missing = match &attrs.default {
+                Some(_) => default_value(&attrs.default),
+                None => quote!(::json_parser::private::missing_field(#type_name, #key)?),
+            };
+            quote! {
+                match #slot {
+                    ::std::option::Option::Some(__value) => __value,
+                    ::std::option::Option::None => #missing,
+                }
+            }
+        };
+        members.push(quote!(#ident: #value));
+    }
+
+    let unknown = if has_flatten {
+        quote!(__flattened.push((__key, __value)))
+    } else if *deny_unknown_fields {
+        quote! {
+            return ::std::result::Result::Err(
+                ::json_parser::private::unknown_field(&__key, #type_name),
+            )
+        }
+    } else {
+        quote!({})
+    };
+    let flattened_buffer = if has_flatten {
+        quote!(let mut __flattened = ::std::vec::Vec::new();)
+    } else {
+        quote!()
+    };
+
+    Ok(quote! {{
+        let __object = match #json {
+            ::json_parser::JsonType::Object(__object) => __object,
+            __other => {
+                return ::std::result::Result::Err(
+                    ::json_parser::private::invalid_type("an object", #type_name, &__other),
+                );
+            }
+        };
+        #(#slots)*
+        #flattened_buffer
+        for (__key, __value) in *__object {
+            match __key.as_str() {
+                #(#arms)*
+                _ => #unknown,
+            }
+        }
+        ::std::result::Result::Ok(#constructor {
+            #(#members,)*
+        })
+    }})
+}
+
+/// Value given to a skipped field or to a missing field marked `default`
+pub(crate) fn default_value(default: &Option<DefaultValue>) -> TokenStream {
+    match default {
+        Some(DefaultValue::Path(path)) => quote!(#path()),
+        _ => quote!(::std::default::Default::default()),
+    }
+}
+
+/// Deserializes an enum with the representation chosen on the container
+fn deserialize_enum(
+    type_name: &str,
+    data: &DataEnum,
+    container: &attr::Container,
+    json: &TokenStream,
+) -> syn::Result<TokenStream> {
+    let content = quote!(__content);
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        let attrs = attr::Variant::from_attributes(&variant.attrs)?;
+        let ident = &variant.ident;
+        let target = Target {
+            constructor: quote!(Self::#ident),
+            type_name: format!("{}::{}", type_name, ident.unraw()),
+            fields: &variant.fields,
+            rename_all: attrs.rename_all,
+            deny_unknown_fields: container.deny_unknown_fields,
+        };
+        let value = deserialize_fields(&target, &content)?;
+        variants.push((variant, attrs, value));
+    }
+
+    let unknown_variant = quote! {
+        __other => ::std::result::Result::Err(
+            ::json_parser::private::unknown_variant(__other, #type_name),
+        ),
+    };
+    let tag = |(variant, attrs, _): &(&syn::Variant, attr::Variant, TokenStream)| {
+        attrs.tag(&variant.ident, container.rename_all)
+    };
+
+    match &container.representation {
+        Representation::External => {
+            let mut unit_arms = Vec::new();
+            let mut arms = Vec::new();
+            for entry in &variants {
+                let (variant, _, value) = entry;
+                let tag = tag(entry);
+                let ident = &variant.ident;
+                if let Fields::Unit = variant.fields {
+                    unit_arms.push(quote!(#tag => ::std::result::Result::Ok(Self::#ident)));
+                }
+                arms.push(quote!(#tag => #value));
+            }
+
+            Ok(quote! {
+                match #json {
+                    ::json_parser::JsonType::Str(__tag) => match __tag.as_str() {
+                        #(#unit_arms,)*
+                        #unknown_variant
+                    },
+                    ::json_parser::JsonType::Object(__object) => {
+                        let mut __entries = (*__object).into_iter();
+                        let (__tag, #content) = match (__entries.next(), __entries.next()) {
+                            (::std::option::Option::Some(__entry), ::std::option::Option::None) => __entry,
+                            _ => {
+                                return ::std::result::Result::Err(
+                                    ::json_parser::private::invalid_value("an object with a single key", #type_name),
+                                );
+                            }
+                        };
+                        match __tag.as_str() {
+                            #(#arms,)*
+                            #unknown_variant
+                        }
+                    }
+                    __other => ::std::result::Result::Err(
+                        ::json_parser::private::invalid_type("a string or an object", #type_name, &__other),
+                    ),
+                }
+            })
+        }
+        Representation::Internal { tag: key } => {
+            let mut arms = Vec::new();
+            for entry in &variants {
+                let (variant, _, value) = entry;
+                let tag = tag(entry);
+                let ident = &variant.ident;
+                let value = match &variant.fields {
+                    Fields::Unit => quote!(::std::result::Result::Ok(Self::#ident)),
+                    Fields::Unnamed(unnamed) if unnamed.unnamed.len() > 1 => {
+                        return Err(syn::Error::new_spanned(
+                            variant,
+                            "internally tagged enums cannot hold tuple variants",
+                        ));
+                    }
+                    _ => value.clone(),
+                };
+                arms.push(quote!(#tag => #value));
+            }
+
+            Ok(quote! {
+                match #json {
+                    ::json_parser::JsonType::Object(__object) => {
+                        let (__tag, #content) =
+                            ::json_parser::private::take_tag(*__object, #key, #type_name)?;
+                        match __tag.as_str() {
+                            #(#arms,)*
+                            #unknown_variant
+                        }
+                    }
+                    __other => ::std::result::Result::Err(
+                        ::json_parser::private::invalid_type("an object", #type_name, &__other),
+                    ),
+                }
+            })
+        }
+        Representation::Adjacent {
+            tag: key,
+            content: content_key,
+        } => {
+            let arms = variants.iter().map(|entry| {
+                let tag = tag(entry);
+                let value = &entry.2;
+                quote!(#tag => #value)
+            });
+
+            Ok(quote! {
+                match #json {
+                    ::json_parser::JsonType::Object(__object) => {
+                        let (__tag, #content) = ::json_parser::private::take_adjacent(
+                            *__object,
+                            #key,
+                            #content_key,
+                            #type_name,
+                        )?;
+                        match __tag.as_str() {
+                            #(#arms,)*
+                            #unknown_variant
+                        }
+                    }
+                    __other => ::std::result::Result::Err(
+                        ::json_parser::private::invalid_type("an object", #type_name, &__other),
+                    ),
+                }
+            })
+        }
+        Representation::Untagged => {
+            let mut attempts = Vec::new();
+            for entry in &variants {
+                let (variant, attrs, value) = entry;
+                let tag = tag(entry);
+                let score = score(variant, attrs, json)?;
+                attempts.push(quote! {
+                    let __attempt = |#content: ::json_parser::JsonType|
+                        -> ::std::result::Result<Self, ::json_parser::error::JsonError> {
+                        #value
+                    };
+                    match __attempt(::std::clone::Clone::clone(&#json)) {
+                        ::std::result::Result::Ok(__value) => {
+                            return ::std::result::Result::Ok(__value);
+                        }
+                        ::std::result::Result::Err(__error) => {
+                            __attempts.push((#tag, #score, __error));
+                        }
+                    }
+                });
+            }
+
+            Ok(quote! {{
+                let mut __attempts: ::std::vec::Vec<(
+                    &str,
+                    ::std::primitive::usize,
+                    ::json_parser::error::JsonError,
+                )> = ::std::vec::Vec::new();
+                #(#attempts)*
+                ::std::result::Result::Err(
+                    ::json_parser::private::untagged_error(#type_name, __attempts),
+                )
+            }})
+        }
+    }
+}
+
+/// How close the value held by `json` is to a variant of an untagged enum
+fn score(
+    variant: &syn::Variant,
+    attrs: &attr::Variant,
+    json: &TokenStream,
+) -> syn::Result<TokenStream> {
+    match &variant.fields {
+        Fields::Named(named) => {
+            let mut keys = Vec::new();
+            for field in &named.named {
+                let field_attrs = attr::Field::from_attributes(&field.attrs)?;
+                if let Some(ident) = &field.ident
+                    && !field_attrs.skip
+                    && !field_attrs.flatten
+                {
+                    keys.push(field_attrs.key(ident, attrs.rename_all));
+                }
+            }
+            Ok(quote!(::json_parser::private::object_score(&#json, &[#(#keys),*])))
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => Ok(quote!(0)),
+        Fields::Unnamed(unnamed) => {
+            let mut length: usize = 0;
+            for field in &unnamed.unnamed {
+                if !attr::Field::from_attributes(&field.attrs)?.skip {
+                    length += 1;
+                }
+            }
+            Ok(quote!(::json_parser::private::array_score(&#json, #length)))
+        }
+        Fields::Unit => Ok(quote!(::std::primitive::usize::from(
+            ::std::matches!(#json, ::json_parser::JsonType::Null)
+        ))),
+    }
+}
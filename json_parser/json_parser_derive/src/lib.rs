@@ -0,0 +1,81 @@
+//! Derive macros for the `json_parser` `Serialize` and `Deserialize` traits
+//!
+//! The macros are re-exported by `json_parser` and should be used from there:
+//!
+//! ```ignore
+//! use json_parser::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Person {
+//!     name: String,
+//! }
+//! ```
+//!
+//! Structs with named fields map to JSON objects, tuple structs to arrays,
+//! newtype structs to their inner value and unit structs to `null`.
+//!
+//! Enums are externally tagged by default: a unit variant is its name as a
+//! string and any other variant is an object with the variant name as the
+//! single key. Other representations are chosen on the enum:
+//!
+//! - `#[json(tag = "type")]`: internally tagged, `{"type": "Variant", ...}`.
+//!   The content must be an object, so tuple variants are rejected and
+//!   serializing a newtype variant holding anything else returns an error,
+//!   except through the infallible `Serialize::serialize` which panics; use
+//!   `json_parser::to_value` to build the tree fallibly.
+//! - `#[json(tag = "t", content = "c")]`: adjacently tagged,
+//!   `{"t": "Variant", "c": content}`, without `"c"` for unit variants.
+//! - `#[json(untagged)]`: the content alone, unit variants being `null`.
+//!   Variants are tried in order and the error of the closest one is reported
+//!   when none matches.
+//!
+//! The generated code can be tuned with `#[json(...)]` attributes:
+//!
+//! - on the container: `rename_all = "camelCase"` (or `"lowercase"`,
+//!   `"UPPERCASE"`, `"PascalCase"`, `"snake_case"`, `"SCREAMING_SNAKE_CASE"`,
+//!   `"kebab-case"`, `"SCREAMING-KEBAB-CASE"`), `deny_unknown_fields`, `tag`,
+//!   `content` and `untagged`
+//! - on a variant: `rename = "name"` and `rename_all = "..."` for its fields
+//! - on a field: `rename = "name"`, `default`, `default = "path::to::fn"`,
+//!   `skip`, `skip_serializing_if = "path::to::fn"` and `flatten`
+//!
+//! A `flatten` field is serialized by merging the members of its object into
+//! the parent object, `null` adds nothing and any other value is kept under
+//! the field name. When deserializing, it is built from every member that does
+//! not match another field.
+//!
+//! `FromDeserializer` reads a struct with named fields straight from a
+//! `json_parser::de::Deserializer`, without going through a `JsonType`. It
+//! accepts the same attributes but `flatten` and the enum representations.
+
+use proc_macro::TokenStream;
+use syn::{DeriveInput, parse_macro_input};
+
+mod attr;
+mod de;
+mod ser;
+mod visit;
+
+#[proc_macro_derive(Serialize, attributes(json))]
+pub fn derive_serialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    ser::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(Deserialize, attributes(json))]
+pub fn derive_deserialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    de::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(FromDeserializer, attributes(json))]
+pub fn derive_from_deserializer(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    visit::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
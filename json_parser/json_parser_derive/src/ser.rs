@@ -0,0 +1,397 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DataEnum, DeriveInput, Fields, parse_quote};
+
+use crate::attr::{self, RenameRule, Representation};
+
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let container = attr::Container::from_attributes(&input.attrs)?;
+    let serializer = quote!(__serializer);
+    let (body, stream_body) = match &input.data {
+        Data::Struct(data) => {
+            container.check_struct(name)?;
+            let values: Vec<_> = data
+                .fields
+                .members()
+                .map(|member| quote!(&self.#member))
+                .collect();
+            (
+                serialize_fields(&data.fields, values.clone(), container.rename_all)?,
+                stream_fields(&data.fields, values, container.rename_all, &serializer)?,
+            )
+        }
+        Data::Enum(data) => (
+            serialize_enum(data, &container)?,
+            stream_enum(data, &container, &serializer)?,
+        ),
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "Serialize cannot be derived for unions",
+            ));
+        }
+    };
+
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::json_parser::Serialize));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::json_parser::Serialize for #name #ty_generics #where_clause {
+            fn serialize(&self) -> ::json_parser::JsonType {
+                #body
+            }
+
+            fn serialize_into<__S: ::json_parser::serializer::Serializer + ?::std::marker::Sized>(
+                &self,
+                #serializer: &mut __S,
+            ) -> ::std::result::Result<(), ::json_parser::error::JsonError> {
+                #stream_body
+            }
+        }
+    })
+}
+
+/// Builds the `JsonType` of a struct or of an enum variant content
+///
+/// `values` holds a reference to each field, in declaration order.
+fn serialize_fields(
+    fields: &Fields,
+    values: Vec<TokenStream>,
+    rename_all: Option<RenameRule>,
+) -> syn::Result<TokenStream> {
+    match fields {
+        Fields::Named(named) => {
+            let mut entries = Vec::new();
+            for (field, value) in named.named.iter().zip(values) {
+                let attrs = attr::Field::from_attributes(&field.attrs)?;
+                let Some(ident) = &field.ident else {
+                    continue;
+                };
+                if attrs.skip {
+                    continue;
+                }
+                let key = attrs.key(ident, rename_all);
+                let entry = if attrs.flatten {
+                    quote! {
+                        ::json_parser::private::flatten_into(
+                            &mut __entries,
+                            #key,
+                            ::json_parser::Serialize::serialize(#value),
+                        );
+                    }
+                } else {
+                    quote! {
+                        __entries.push((
+                            ::std::string::String::from(#key),
+                            ::json_parser::Serialize::serialize(#value),
+                        ));
+                    }
+                };
+                entries.push(match &attrs.skip_serializing_if {
+                    Some(predicate) => quote!(if !#predicate(#value) { #entry }),
+                    None => entry,
+                });
+            }
+            Ok(quote! {{
+                let mut __entries = ::std::vec::Vec::new();
+                #(#entries)*
+                ::json_parser::JsonType::Object(::std::boxed::Box::new(
+                    ::json_parser::Object::new(__entries),
+                ))
+            }})
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+            let value = &values[0];
+            Ok(quote!(::json_parser::Serialize::serialize(#value)))
+        }
+        Fields::Unnamed(unnamed) => {
+            let mut elements = Vec::new();
+            for (field, value) in unnamed.unnamed.iter().zip(values) {
+                if !attr::Field::from_attributes(&field.attrs)?.skip {
+                    elements.push(quote!(::json_parser::Serialize::serialize(#value)));
+                }
+            }
+            Ok(quote!(::json_parser::JsonType::Array(
+                ::std::vec![#(#elements),*]
+            )))
+        }
+        Fields::Unit => Ok(quote!(::json_parser::JsonType::Null)),
+    }
+}
+
+/// Serializes an enum with the representation chosen on the container
+fn serialize_enum(data: &DataEnum, container: &attr::Container) -> syn::Result<TokenStream> {
+    if data.variants.is_empty() {
+        return Ok(quote!(match *self {}));
+    }
+
+    let mut arms = Vec::new();
+    for variant in &data.variants {
+        let attrs = attr::Variant::from_attributes(&variant.attrs)?;
+        let ident = &variant.ident;
+        let tag = attrs.tag(ident, container.rename_all);
+        let (pattern, values) = bindings(&variant.fields)?;
+        let content = match &variant.fields {
+            Fields::Unit => None,
+            fields => Some(serialize_fields(fields, values, attrs.rename_all)?),
+        };
+        let value = match (&container.representation, content) {
+            (Representation::External, None) => string(&tag),
+            (Representation::External, Some(content)) => object(vec![(tag, content)]),
+            (Representation::Internal { tag: key }, content) => {
+                if let Fields::Unnamed(unnamed) = &variant.fields
+                    && unnamed.unnamed.len() > 1
+                {
+                    return Err(syn::Error::new_spanned(
+                        variant,
+                        "internally tagged enums cannot hold tuple variants",
+                    ));
+                }
+                let content = content.unwrap_or(quote!(::json_parser::JsonType::Null));
+                quote!(::json_parser::private::tag_content(#key, #tag, #content))
+            }
+            (Representation::Adjacent { tag: key, .. }, None) => {
+                object(vec![(key.clone(), string(&tag))])
+            }
+            (
+                Representation::Adjacent {
+                    tag: key,
+                    content: value,
+                },
+                Some(content),
+            ) => object(vec![(key.clone(), string(&tag)), (value.clone(), content)]),
+            (Representation::Untagged, None) => quote!(::json_parser::JsonType::Null),
+            (Representation::Untagged, Some(content)) => content,
+        };
+        arms.push(quote!(Self::#ident #pattern => #value));
+    }
+
+    Ok(quote! {
+        match self {
+            #(#arms,)*
+        }
+    })
+}
+
+fn string(value: &str) -> TokenStream {
+    quote!(::json_parser::JsonType::Str(::std::string::String::from(#value)))
+}
+
+fn object(entries: Vec<(String, TokenStream)>) -> TokenStream {
+    let entries = entries
+        .into_iter()
+        .map(|(key, value)| quote!((::std::string::String::from(#key), #value)));
+    quote! {
+        ::json_parser::JsonType::Object(::std::boxed::Box::new(
+            ::json_parser::Object::new(::std::vec![#(#entries),*]),
+        ))
+    }
+}
+
+/// Streams a struct or an enum variant content to the serializer held by
+/// `serializer`, the generated expression evaluates to a `Result<(), JsonError>`
+fn stream_fields(
+    fields: &Fields,
+    values: Vec<TokenStream>,
+    rename_all: Option<RenameRule>,
+    serializer: &TokenStream,
+) -> syn::Result<TokenStream> {
+    match fields {
+        Fields::Named(_) => {
+            let map = quote!(__map);
+            let entries = stream_entries(fields, values, rename_all, &map)?;
+            Ok(quote! {{
+                let mut #map = ::json_parser::serializer::Serializer::serialize_map(&mut *#serializer)?;
+                #(#entries)*
+                ::json_parser::serializer::SerializeMap::end(#map)
+            }})
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+            let value = &values[0];
+            Ok(quote!(::json_parser::Serialize::serialize_into(#value, &mut *#serializer)))
+        }
+        Fields::Unnamed(unnamed) => {
+            let mut elements = Vec::new();
+            for (field, value) in unnamed.unnamed.iter().zip(values) {
+                if !attr::Field::from_attributes(&field.attrs)?.skip {
+                    elements.push(quote! {
+                        ::json_parser::serializer::SerializeSeq::serialize_element(&mut __seq, #value)?;
+                    });
+                }
+            }
+            Ok(quote! {{
+                let mut __seq = ::json_parser::serializer::Serializer::serialize_seq(&mut *#serializer)?;
+                #(#elements)*
+                ::json_parser::serializer::SerializeSeq::end(__seq)
+            }})
+        }
+        Fields::Unit => Ok(
+            quote!(::json_parser::serializer::Serializer::serialize_null(
+                &mut *#serializer
+            )),
+        ),
+    }
+}
+
+/// Statements writing each named field to the `SerializeMap` held by `map`
+fn stream_entries(
+    fields: &Fields,
+    values: Vec<TokenStream>,
+    rename_all: Option<RenameRule>,
+    map: &TokenStream,
+) -> syn::Result<Vec<TokenStream>> {
+    let mut entries = Vec::new();
+    for (field, value) in fields.iter().zip(values) {
+        let attrs = attr::Field::from_attributes(&field.attrs)?;
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        if attrs.skip {
+            continue;
+        }
+        let key = attrs.key(ident, rename_all);
+        let entry = if attrs.flatten {
+            quote! {
+                ::json_parser::private::flatten_into_map(
+                    &mut #map,
+                    #key,
+                    ::json_parser::to_value(#value)?,
+                )?;
+            }
+        } else {
+            quote! {
+                ::json_parser::serializer::SerializeMap::serialize_entry(&mut #map, #key, #value)?;
+            }
+        };
+        entries.push(match &attrs.skip_serializing_if {
+            Some(predicate) => quote!(if !#predicate(#value) { #entry }),
+            None => entry,
+        });
+    }
+    Ok(entries)
+}
+
+/// Streams an enum with the representation chosen on the container
+fn stream_enum(
+    data: &DataEnum,
+    container: &attr::Container,
+    serializer: &TokenStream,
+) -> syn::Result<TokenStream> {
+    if data.variants.is_empty() {
+        return Ok(quote!(match *self {}));
+    }
+
+    let map = quote!(__map);
+    let start_map = quote! {
+        let mut #map = ::json_parser::serializer::Serializer::serialize_map(&mut *#serializer)?;
+    };
+    let end_map = quote!(::json_parser::serializer::SerializeMap::end(#map));
+    let inner = quote!(__inner);
+
+    let mut arms = Vec::new();
+    for variant in &data.variants {
+        let attrs = attr::Variant::from_attributes(&variant.attrs)?;
+        let ident = &variant.ident;
+        let tag = attrs.tag(ident, container.rename_all);
+        let (pattern, values) = bindings(&variant.fields)?;
+        let is_unit = matches!(variant.fields, Fields::Unit);
+        let value = match &container.representation {
+            Representation::External if is_unit => quote! {
+                ::json_parser::serializer::Serializer::serialize_str(&mut *#serializer, #tag)
+            },
+            Representation::External => {
+                let content = stream_fields(&variant.fields, values, attrs.rename_all, &inner)?;
+                quote! {{
+                    #start_map
+                    {
+                        let #inner = ::json_parser::serializer::SerializeMap::serialize_key(&mut #map, #tag)?;
+                        #content?;
+                    }
+                    #end_map
+                }}
+            }
+            Representation::Internal { tag: key } => match &variant.fields {
+                Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+                    let value = &values[0];
+                    quote! {{
+                        #start_map
+                        ::json_parser::private::tag_content_into_map(
+                            &mut #map,
+                            #key,
+                            #tag,
+                            ::json_parser::to_value(#value)?,
+                        )?;
+                        #end_map
+                    }}
+                }
+                fields => {
+                    let entries = stream_entries(fields, values, attrs.rename_all, &map)?;
+                    quote! {{
+                        #start_map
+                        ::json_parser::serializer::SerializeMap::serialize_entry(&mut #map, #key, #tag)?;
+                        #(#entries)*
+                        #end_map
+                    }}
+                }
+            },
+            Representation::Adjacent { tag: key, content } => {
+                let value = if is_unit {
+                    quote!()
+                } else {
+                    let value = stream_fields(&variant.fields, values, attrs.rename_all, &inner)?;
+                    quote! {{
+                        let #inner = ::json_parser::serializer::SerializeMap::serialize_key(&mut #map, #content)?;
+                        #value?;
+                    }}
+                };
+                quote! {{
+                    #start_map
+                    ::json_parser::serializer::SerializeMap::serialize_entry(&mut #map, #key, #tag)?;
+                    #value
+                    #end_map
+                }}
+            }
+            Representation::Untagged => {
+                stream_fields(&variant.fields, values, attrs.rename_all, serializer)?
+            }
+        };
+        arms.push(quote!(Self::#ident #pattern => #value));
+    }
+
+    Ok(quote! {
+        match self {
+            #(#arms,)*
+        }
+    })
+}
+
+/// Builds the match pattern binding the fields of an enum variant, along with
+/// the name bound to each field
+///
+/// Skipped fields are matched with `_` so they do not trigger unused variable
+/// warnings.
+fn bindings(fields: &Fields) -> syn::Result<(TokenStream, Vec<TokenStream>)> {
+    let mut patterns = Vec::new();
+    let mut values = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let binding = format_ident!("__field{}", index);
+        let skip = attr::Field::from_attributes(&field.attrs)?.skip;
+        let pattern = if skip { quote!(_) } else { quote!(#binding) };
+        patterns.push(match &field.ident {
+            Some(ident) => quote!(#ident: #pattern),
+            None => pattern,
+        });
+        values.push(quote!(#binding));
+    }
+
+    let pattern = match fields {
+        Fields::Named(_) => quote!({ #(#patterns),* }),
+        Fields::Unnamed(_) => quote!((#(#patterns),*)),
+        Fields::Unit => quote!(),
+    };
+    Ok((pattern, values))
+}
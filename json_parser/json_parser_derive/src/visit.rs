@@ -0,0 +1,151 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{Data, DeriveInput, Fields, GenericParam, Lifetime, LifetimeParam, parse_quote};
+
+use crate::attr;
+use crate::de::default_value;
+
+/// Generates a `FromDeserializer` impl reading the members of an object
+/// straight from the deserializer
+///
+/// Only structs with named fields are supported, without `flatten`.
+pub fn expand(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let type_name = name.unraw().to_string();
+    let container = attr::Container::from_attributes(&input.attrs)?;
+    container.check_struct(name)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => &named.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "FromDeserializer can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "FromDeserializer can only be derived for structs with named fields",
+            ));
+        }
+    };
+
+    let mut slots = Vec::new();
+    let mut arms = Vec::new();
+    let mut members = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let attrs = attr::Field::from_attributes(&field.attrs)?;
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        if attrs.flatten {
+            return Err(syn::Error::new_spanned(
+                field,
+                "flatten is not supported by FromDeserializer",
+            ));
+        }
+        if attrs.skip {
+            let value = default_value(&attrs.default);
+            members.push(quote!(#ident: #value));
+            continue;
+        }
+
+        let slot = format_ident!("__field{}", index);
+        let ty = &field.ty;
+        let key = attrs.key(ident, container.rename_all);
+        slots.push(quote! {
+            let mut #slot: ::std::option::Option<#ty> = ::std::option::Option::None;
+        });
+        arms.push(quote! {
+            #key => {
+                #slot = ::std::option::Option::Some(
+                    ::json_parser::de::MapAccess::next_value(&mut __map)?,
+                );
+            }
+        });
+        let missing = match &attrs.default {
+            Some(_) => default_value(&attrs.default),
+            None => quote!(::json_parser::private::missing_visited_field(#type_name, #key)?),
+        };
+        members.push(quote! {
+            #ident: match #slot {
+                ::std::option::Option::Some(__value) => __value,
+                ::std::option::Option::None => #missing,
+            }
+        });
+    }
+
+    let unknown = if container.deny_unknown_fields {
+        quote! {
+            return ::std::result::Result::Err(
+                ::json_parser::private::unknown_field(&__key, #type_name),
+            )
+        }
+    } else {
+        quote! {{
+            ::json_parser::de::MapAccess::next_value::<::json_parser::de::IgnoredAny>(&mut __map)?;
+        }}
+    };
+    let expecting = format!("an object for {}", type_name);
+
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let mut generics = input.generics.clone();
+    let de: Lifetime = parse_quote!('de);
+    let mut de_param = LifetimeParam::new(de.clone());
+    for lifetime in input.generics.lifetimes() {
+        de_param.bounds.push(lifetime.lifetime.clone());
+    }
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(parse_quote!(::json_parser::de::FromDeserializer<#de>));
+    }
+    generics.params.insert(0, GenericParam::Lifetime(de_param));
+    let (impl_generics, visitor_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::json_parser::de::FromDeserializer<#de> for #name #ty_generics #where_clause {
+            fn from_deserializer<__D: ::json_parser::de::Deserializer<#de>>(
+                __deserializer: __D,
+            ) -> ::std::result::Result<Self, ::json_parser::error::JsonError> {
+                struct __Visitor #impl_generics (
+                    ::std::marker::PhantomData<fn() -> (&#de (), #name #ty_generics)>,
+                ) #where_clause;
+
+                impl #impl_generics ::json_parser::de::Visitor<#de> for __Visitor #visitor_generics #where_clause {
+                    type Value = #name #ty_generics;
+
+                    fn expecting(&self) -> &'static str {
+                        #expecting
+                    }
+
+                    fn visit_map<__A: ::json_parser::de::MapAccess<#de>>(
+                        self,
+                        mut __map: __A,
+                    ) -> ::std::result::Result<Self::Value, ::json_parser::error::JsonError> {
+                        #(#slots)*
+                        while let ::std::option::Option::Some(__key) =
+                            ::json_parser::de::MapAccess::next_key(&mut __map)?
+                        {
+                            match &*__key {
+                                #(#arms)*
+                                _ => #unknown,
+                            }
+                        }
+                        ::std::result::Result::Ok(#name {
+                            #(#members,)*
+                        })
+                    }
+                }
+
+                ::json_parser::de::Deserializer::deserialize_any(
+                    __deserializer,
+                    __Visitor(::std::marker::PhantomData),
+                )
+            }
+        }
+    })
+}
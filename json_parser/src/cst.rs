@@ -0,0 +1,1021 @@
+//! Lossless editing of a JSON document
+//!
+//! A [`Document`] keeps every token of the input along with the whitespace,
+//! and in JSON5 the comments, around it. Edits only touch the values they
+//! target, so writing the document back gives the input byte for byte apart
+//! from them. New values are written compactly and take the layout of their
+//! neighbours.
+//!
+//! # Examples
+//!
+//! ```
+//! use json_parser::cst::Document;
+//! use json_parser::JsonType;
+//!
+//! let mut document = Document::parse("{\n  \"name\": \"app\",\n  \"tags\": [\"a\"]\n}\n").unwrap();
+//!
+//! document.set("/name", JsonType::Str("service".to_string())).unwrap();
+//! document.insert_at("/port", JsonType::Null).unwrap();
+//! document.remove_at("/tags/0").unwrap();
+//!
+//! assert_eq!(
+//!     "{\n  \"name\": \"service\",\n  \"tags\": [],\n  \"port\": null\n}\n",
+//!     document.to_string()
+//! );
+//! ```
+
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+use crate::error::JsonError;
+use crate::options::{DuplicateKeys, ParserOptions};
+use crate::pointer::{self, PointerError};
+use crate::serializer::serialize_json;
+use crate::token::tokenizer::{Position, SpannedToken, StrSource, Token, Tokenizer};
+use crate::types::Num;
+use crate::{JsonType, from_string_with_options};
+
+/// A parsed document which writes back to its exact text
+///
+/// Values are addressed with JSON Pointers as in [`JsonType::pointer`]. When
+/// an object repeats a key, the member edited is the one
+/// [`DuplicateKeys`] keeps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    before: String,
+    root: Node,
+    after: String,
+    options: ParserOptions,
+}
+
+/// A value along with the trivia inside it
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// The exact text of a string, number, boolean or null
+    Scalar(String),
+    Array(Container<Element>),
+    Object(Container<Member>),
+}
+
+/// The items of an array or an object
+#[derive(Debug, Clone, PartialEq)]
+struct Container<T> {
+    items: Vec<T>,
+    /// The trivia before the closing bracket which follows a trailing comma,
+    /// or the whole inside of an empty container
+    end: String,
+    trailing_comma: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Element {
+    before: String,
+    value: Node,
+    after: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Member {
+    before_key: String,
+    /// The unescaped key
+    key: String,
+    /// The key as written in the input
+    raw_key: String,
+    after_key: String,
+    before_value: String,
+    value: Node,
+    after_value: String,
+}
+
+/// The trivia around an item, which the items moved next to it take over
+trait Item {
+    fn before(&mut self) -> &mut String;
+    fn after(&mut self) -> &mut String;
+
+    /// Copies the trivia inside `neighbour` which is not around it
+    fn align(&mut self, _neighbour: &Self) {}
+}
+
+impl Item for Element {
+    fn before(&mut self) -> &mut String {
+        &mut self.before
+    }
+
+    fn after(&mut self) -> &mut String {
+        &mut self.after
+    }
+}
+
+impl Item for Member {
+    fn before(&mut self) -> &mut String {
+        &mut self.before_key
+    }
+
+    fn after(&mut self) -> &mut String {
+        &mut self.after_value
+    }
+
+    fn align(&mut self, neighbour: &Self) {
+        self.after_key = neighbour.after_key.clone();
+        self.before_value = neighbour.before_value.clone();
+    }
+}
+
+impl<T: Item> Container<T> {
+    fn new(items: Vec<T>, end: String, trailing_comma: bool) -> Self {
+        Self {
+            items,
+            end,
+            trailing_comma,
+        }
+    }
+
+    /// Inserts `item` at `index`, copying the trivia of its neighbours so it
+    /// lines up with them
+    fn insert(&mut self, index: usize, mut item: T) {
+        let len = self.items.len();
+        if let Some(neighbour) = self.items.get(index.min(len.saturating_sub(1))) {
+            item.align(neighbour);
+        }
+        if len == 0 {
+            self.items.push(item);
+        } else if index == len {
+            let last = &mut self.items[len - 1];
+            let (before, after) = split_trailing(std::mem::take(last.after()), last.before());
+            *item.before() = before;
+            *item.after() = after;
+            self.items.push(item);
+        } else if index == 0 {
+            *item.before() = std::mem::take(self.items[0].before());
+            let separator = match self.items.get_mut(1) {
+                Some(next) => next.before().clone(),
+                None => item.before().clone(),
+            };
+            *self.items[0].before() = separator;
+            self.items.insert(0, item);
+        } else {
+            *item.before() = self.items[index].before().clone();
+            self.items.insert(index, item);
+        }
+    }
+
+    /// Removes the item at `index`, the trivia after the brackets stays
+    fn remove(&mut self, index: usize) {
+        let mut removed = self.items.remove(index);
+        let len = self.items.len();
+        if len == 0 {
+            self.end = std::mem::take(removed.after()) + &self.end;
+            self.trailing_comma = false;
+        } else if index == len {
+            *self.items[len - 1].after() = std::mem::take(removed.after());
+        } else if index == 0 {
+            *self.items[0].before() = std::mem::take(removed.before());
+        }
+    }
+}
+
+/// Splits the trivia after the last item of a container between the item
+/// appended to it and the closing bracket, given `before` the trivia before
+/// the last item
+///
+/// The comments stay with the last item, right after the comma which now
+/// follows it, and the new item starts a line like it when they end one. The
+/// line break and indentation before the closing bracket go after the new
+/// item.
+fn split_trailing(trailing: String, before: &str) -> (String, String) {
+    let comments = trailing.trim_end().len();
+    let rest = &trailing[comments..];
+    let before = match rest.find('\n') {
+        Some(newline) if comments > 0 => {
+            let indent = before.rfind('\n').map_or("", |index| &before[index + 1..]);
+            format!("{}{}{}", &trailing[..comments], &rest[..=newline], indent)
+        }
+        _ => format!("{}{}", &trailing[..comments], before),
+    };
+    (before, rest.to_string())
+}
+
+impl Node {
+    /// Builds the compact text of `value`, JSON5 allowing non-finite floats
+    ///
+    /// `levels` is the number of arrays and objects `value` may still nest,
+    /// which keeps the recursion of this and of writing the node bounded.
+    fn new(value: &JsonType, json5: bool, levels: Levels) -> Result<Node, PointerError> {
+        let inner = match value {
+            JsonType::Array(_) | JsonType::Object(_) => levels.enter()?,
+            _ => levels,
+        };
+        Ok(match value {
+            JsonType::Array(values) => {
+                let items = values
+                    .iter()
+                    .map(|value| Element::new(value, json5, inner))
+                    .collect::<Result<_, _>>()?;
+                Node::Array(Container::new(items, String::new(), false))
+            }
+            JsonType::Object(object) => {
+                let items = object
+                    .iter()
+                    .map(|(key, value)| Member::new(key, value, json5, inner))
+                    .collect::<Result<_, _>>()?;
+                Node::Object(Container::new(items, String::new(), false))
+            }
+            value => Node::Scalar(scalar(value, json5)?),
+        })
+    }
+
+    /// Follows `token` one level down
+    fn child(&mut self, token: &str, duplicate_keys: DuplicateKeys) -> Option<&mut Node> {
+        match self {
+            Node::Object(object) => {
+                let index = member(&object.items, token, duplicate_keys)?;
+                Some(&mut object.items[index].value)
+            }
+            Node::Array(array) => {
+                let index = pointer::index(token).ok()?;
+                Some(&mut array.items.get_mut(index)?.value)
+            }
+            Node::Scalar(_) => None,
+        }
+    }
+}
+
+/// An empty scalar, standing for a value not read yet
+impl Default for Node {
+    fn default() -> Self {
+        Node::Scalar(String::new())
+    }
+}
+
+/// The nesting left to a value built by [`Node::new`], out of the
+/// `max_depth` of the document
+#[derive(Clone, Copy)]
+struct Levels {
+    left: usize,
+    max: usize,
+}
+
+impl Levels {
+    /// The levels left to the values nested in an array or object
+    fn enter(self) -> Result<Levels, PointerError> {
+        match self.left.checked_sub(1) {
+            Some(left) => Ok(Levels { left, ..self }),
+            None => Err(PointerError::InvalidValue(format!(
+                "nesting deeper than {} levels",
+                self.max
+            ))),
+        }
+    }
+}
+
+impl Element {
+    fn new(value: &JsonType, json5: bool, levels: Levels) -> Result<Element, PointerError> {
+        Ok(Element {
+            before: String::new(),
+            value: Node::new(value, json5, levels)?,
+            after: String::new(),
+        })
+    }
+}
+
+impl Member {
+    fn new(
+        key: &str,
+        value: &JsonType,
+        json5: bool,
+        levels: Levels,
+    ) -> Result<Member, PointerError> {
+        Ok(Member {
+            key: key.to_string(),
+            raw_key: scalar(&JsonType::Str(key.to_string()), json5)?,
+            value: Node::new(value, json5, levels)?,
+            ..Member::default()
+        })
+    }
+}
+
+/// Writes a scalar, non-finite floats being written as their literals in
+/// JSON5 and rejected otherwise
+fn scalar(value: &JsonType, json5: bool) -> Result<String, PointerError> {
+    match value {
+        JsonType::Num(Num::Float(value)) if json5 && value.is_nan() => Ok("NaN".to_string()),
+        JsonType::Num(Num::Float(value)) if json5 && value.is_infinite() => Ok(if *value > 0.0 {
+            "Infinity"
+        } else {
+            "-Infinity"
+        }
+        .to_string()),
+        value => serialize_json(value).map_err(|error| {
+            PointerError::InvalidValue(match error {
+                JsonError::SerializationError(message) => message,
+                error => error.to_string(),
+            })
+        }),
+    }
+}
+
+/// Finds the member `key` refers to, a repeated key being resolved by the
+/// `duplicate_keys` policy
+fn member(members: &[Member], key: &str, duplicate_keys: DuplicateKeys) -> Option<usize> {
+    let mut matches = members.iter().enumerate().filter(|(_, m)| m.key == key);
+    let (index, _) = match duplicate_keys {
+        DuplicateKeys::First => matches.next(),
+        DuplicateKeys::Last | DuplicateKeys::Error => matches.next_back(),
+    }?;
+    Some(index)
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Node::Scalar(text) => write!(f, "{}", text),
+            Node::Array(array) => {
+                write!(f, "[")?;
+                array.fmt_items(f, |f, element| {
+                    write!(f, "{}{}{}", element.before, element.value, element.after)
+                })?;
+                write!(f, "]")
+            }
+            Node::Object(object) => {
+                write!(f, "{{")?;
+                object.fmt_items(f, |f, member| {
+                    write!(
+                        f,
+                        "{}{}{}:{}{}{}",
+                        member.before_key,
+                        member.raw_key,
+                        member.after_key,
+                        member.before_value,
+                        member.value,
+                        member.after_value
+                    )
+                })?;
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+impl<T> Container<T> {
+    /// Writes the items separated by commas and the trivia before the end
+    fn fmt_items(
+        &self,
+        f: &mut Formatter,
+        fmt_item: impl Fn(&mut Formatter, &T) -> std::fmt::Result,
+    ) -> std::fmt::Result {
+        for (index, item) in self.items.iter().enumerate() {
+            fmt_item(f, item)?;
+            if index + 1 < self.items.len() || self.trailing_comma {
+                write!(f, ",")?;
+            }
+        }
+        write!(f, "{}", self.end)
+    }
+}
+
+/// Writes the document back, including the edits made to it
+impl Display for Document {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.before, self.root, self.after)
+    }
+}
+
+impl Document {
+    pub fn parse(input: &str) -> Result<Document, JsonError> {
+        Self::parse_with_options(input, ParserOptions::default())
+    }
+
+    /// Parses `input`, returning the errors
+    /// [`Parser`](crate::parser::Parser) returns on it
+    ///
+    /// Writing, cloning and dropping a document recurse once per nesting
+    /// level, so documents and edits are limited to
+    /// [`RECURSION_LIMIT`](crate::RECURSION_LIMIT) levels even when
+    /// `max_depth` is `None` or higher. A deeper input is a
+    /// `JsonError::DepthLimitExceeded` and a deeper edit a
+    /// [`PointerError::InvalidValue`].
+    pub fn parse_with_options(input: &str, options: ParserOptions) -> Result<Document, JsonError> {
+        let mut tokens = Tokens {
+            input,
+            tokenizer: Tokenizer::with_options(input, options.clone()),
+            end: 0,
+        };
+        let (before, first) = tokens.first()?;
+        let root = tokens.build(first)?;
+        tokens.finish()?;
+        Ok(Document {
+            before,
+            root,
+            after: input[tokens.end..].to_string(),
+            options,
+        })
+    }
+
+    /// Builds the [`JsonType`] the document holds
+    pub fn to_json(&self) -> Result<JsonType, JsonError> {
+        from_string_with_options(&self.to_string(), self.options.clone())
+    }
+
+    /// Replaces the value `pointer` refers to, keeping the trivia around it
+    ///
+    /// Non-finite floats are only written in JSON5 documents, they are
+    /// rejected with [`PointerError::InvalidValue`] otherwise.
+    pub fn set(&mut self, pointer: &str, value: JsonType) -> Result<(), PointerError> {
+        let tokens = pointer::parse(pointer)?;
+        let value = Node::new(&value, self.options.json5, self.levels(tokens.len()))?;
+        let node = self
+            .node_mut(&tokens)
+            .ok_or_else(|| PointerError::NotFound(pointer.to_string()))?;
+        *node = value;
+        Ok(())
+    }
+
+    /// Adds `value` where `pointer` refers to, like
+    /// [`JsonType::insert_at`]: an object member is inserted or replaced and
+    /// an array element is inserted before the one at the index, or appended
+    /// for `-`
+    pub fn insert_at(&mut self, pointer: &str, value: JsonType) -> Result<(), PointerError> {
+        let mut tokens = pointer::parse(pointer)?;
+        let json5 = self.options.json5;
+        let levels = self.levels(tokens.len());
+        let Some(last) = tokens.pop() else {
+            self.root = Node::new(&value, json5, levels)?;
+            return Ok(());
+        };
+        let duplicate_keys = self.options.duplicate_keys;
+        let parent = self
+            .node_mut(&tokens)
+            .ok_or_else(|| PointerError::NotFound(pointer.to_string()))?;
+        match parent {
+            Node::Object(object) => match member(&object.items, &last, duplicate_keys) {
+                Some(index) => object.items[index].value = Node::new(&value, json5, levels)?,
+                None => {
+                    let index = object.items.len();
+                    object.insert(index, Member::new(&last, &value, json5, levels)?);
+                }
+            },
+            Node::Array(array) => {
+                let index = match last.as_str() {
+                    "-" => array.items.len(),
+                    token => pointer::index(token)?,
+                };
+                if index > array.items.len() {
+                    return Err(PointerError::InvalidIndex(last));
+                }
+                array.insert(index, Element::new(&value, json5, levels)?);
+            }
+            Node::Scalar(_) => return Err(PointerError::NotFound(pointer.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Removes the value `pointer` refers to along with its trivia
+    pub fn remove_at(&mut self, pointer: &str) -> Result<(), PointerError> {
+        let mut tokens = pointer::parse(pointer)?;
+        let Some(last) = tokens.pop() else {
+            self.root = Node::Scalar("null".to_string());
+            return Ok(());
+        };
+        let duplicate_keys = self.options.duplicate_keys;
+        let parent = self
+            .node_mut(&tokens)
+            .ok_or_else(|| PointerError::NotFound(pointer.to_string()))?;
+        match parent {
+            Node::Object(object) => {
+                let index = member(&object.items, &last, duplicate_keys)
+                    .ok_or_else(|| PointerError::NotFound(pointer.to_string()))?;
+                object.remove(index);
+            }
+            Node::Array(array) => {
+                let index = pointer::index(&last)?;
+                if index >= array.items.len() {
+                    return Err(PointerError::InvalidIndex(last));
+                }
+                array.remove(index);
+            }
+            Node::Scalar(_) => return Err(PointerError::NotFound(pointer.to_string())),
+        }
+        Ok(())
+    }
+
+    /// The nesting left to a value inside `containers` arrays and objects
+    fn levels(&self, containers: usize) -> Levels {
+        let max = self.options.recursion_limit();
+        Levels {
+            left: max.saturating_sub(containers),
+            max,
+        }
+    }
+
+    fn node_mut(&mut self, tokens: &[String]) -> Option<&mut Node> {
+        let duplicate_keys = self.options.duplicate_keys;
+        let mut node = &mut self.root;
+        for token in tokens {
+            node = node.child(token, duplicate_keys)?;
+        }
+        Some(node)
+    }
+}
+
+/// An array or object being built, along with the item being read
+enum Frame {
+    Array {
+        items: Vec<Element>,
+        before: String,
+    },
+    /// `keys` holds the keys read so far when repeating one is an error
+    Object {
+        items: Vec<Member>,
+        member: Box<Member>,
+        key_start: Position,
+        keys: HashSet<String>,
+    },
+}
+
+impl Frame {
+    fn close(&self) -> Token {
+        match self {
+            Frame::Array { .. } => Token::CloseBracket,
+            Frame::Object { .. } => Token::CloseCurlybracket,
+        }
+    }
+}
+
+/// Reads the tokens of the input along with the trivia before each of them
+struct Tokens<'a> {
+    input: &'a str,
+    tokenizer: Tokenizer<StrSource<'a>>,
+    /// Where the last token read ends
+    end: usize,
+}
+
+impl Tokens<'_> {
+    fn next(&mut self) -> Result<(String, SpannedToken), JsonError> {
+        let Some(token) = self.tokenizer.next() else {
+            return Err(JsonError::UnexpectedEndOfJson(self.tokenizer.position()));
+        };
+        let token = token?;
+        let trivia = self.input[self.end..token.span.start.offset].to_string();
+        self.end = token.span.end.offset;
+        Ok((trivia, token))
+    }
+
+    /// Reads the first token, the input must not be empty
+    fn first(&mut self) -> Result<(String, SpannedToken), JsonError> {
+        match self.next() {
+            Err(JsonError::UnexpectedEndOfJson(position)) => Err(JsonError::EmptyInput(position)),
+            result => result,
+        }
+    }
+
+    /// Checks that only trivia follows the value
+    fn finish(&mut self) -> Result<(), JsonError> {
+        match self.tokenizer.next().transpose()? {
+            Some(token) => Err(JsonError::TrailingToken(token.token, token.span.start)),
+            None => Ok(()),
+        }
+    }
+
+    fn raw(&self, token: &SpannedToken) -> String {
+        self.input[token.span.start.offset..token.span.end.offset].to_string()
+    }
+
+    /// Reads the key of the `members`-th member of an object and the colon
+    /// after it, returning the member without its value, where its key
+    /// starts and the token starting the value
+    fn key(
+        &mut self,
+        before_key: String,
+        token: SpannedToken,
+        members: usize,
+    ) -> Result<(Member, Position, SpannedToken), JsonError> {
+        let start = token.span.start;
+        if let Some(max) = self.tokenizer.options().max_object_members
+            && members > max
+        {
+            return Err(JsonError::TooManyMembers(max, start));
+        }
+        let raw_key = self.raw(&token);
+        let (Token::Str(key) | Token::Identifier(key)) = token.token else {
+            return Err(JsonError::KeyError(token.token, start));
+        };
+        let (after_key, colon) = self.next()?;
+        if colon.token != Token::Colon {
+            return Err(JsonError::CollonError(colon.token, colon.span.start));
+        }
+        let (before_value, value) = self.next()?;
+        let member = Member {
+            before_key,
+            key,
+            raw_key,
+            after_key,
+            before_value,
+            ..Member::default()
+        };
+        Ok((member, start, value))
+    }
+
+    /// Builds the value starting with `token`, keeping the open arrays and
+    /// objects on a stack like [`Parser`](crate::parser::Parser) does and
+    /// reporting the same errors
+    fn build(&mut self, mut token: SpannedToken) -> Result<Node, JsonError> {
+        let options = self.tokenizer.options();
+        let max_depth = options.recursion_limit();
+        let duplicate_keys = options.duplicate_keys;
+        let json5 = options.json5;
+        let mut stack: Vec<Frame> = Vec::new();
+        loop {
+            let mut node = match token.token {
+                Token::OpenCurlybracket | Token::OpenBracket if stack.len() >= max_depth => {
+                    return Err(JsonError::DepthLimitExceeded(max_depth, token.span.start));
+                }
+                Token::OpenCurlybracket => {
+                    let (trivia, next) = self.next()?;
+                    if next.token == Token::CloseCurlybracket {
+                        Node::Object(Container::new(Vec::new(), trivia, false))
+                    } else {
+                        let (member, key_start, value) = self.key(trivia, next, 1)?;
+                        stack.push(Frame::Object {
+                            items: Vec::new(),
+                            member: Box::new(member),
+                            key_start,
+                            keys: HashSet::new(),
+                        });
+                        token = value;
+                        continue;
+                    }
+                }
+                Token::OpenBracket => {
+                    let (trivia, next) = self.next()?;
+                    if next.token == Token::CloseBracket {
+                        Node::Array(Container::new(Vec::new(), trivia, false))
+                    } else {
+                        stack.push(Frame::Array {
+                            items: Vec::new(),
+                            before: trivia,
+                        });
+                        token = next;
+                        continue;
+                    }
+                }
+                Token::Str(_)
+                | Token::Int(_)
+                | Token::UInt(_)
+                | Token::Float(_)
+                | Token::Decimal(_)
+                | Token::Bool(_)
+                | Token::Null => Node::Scalar(self.raw(&token)),
+                other => return Err(JsonError::ValueError(other, token.span.start)),
+            };
+
+            // Attaches the value to the container it belongs to, closing the
+            // containers which end after it
+            loop {
+                let Some(frame) = stack.last_mut() else {
+                    return Ok(node);
+                };
+                if let Frame::Object {
+                    member,
+                    key_start,
+                    keys,
+                    ..
+                } = frame
+                    && duplicate_keys == DuplicateKeys::Error
+                    && !keys.insert(member.key.clone())
+                {
+                    return Err(JsonError::DuplicateKey(
+                        std::mem::take(&mut member.key),
+                        *key_start,
+                    ));
+                }
+                let close = frame.close();
+                let (after, separator) = self.next()?;
+                let comma = separator.token == Token::Comma;
+                let (trivia, next) = if comma {
+                    let (trivia, next) = self.next()?;
+                    // Only JSON5 allows a comma before the closing bracket
+                    match next.token {
+                        Token::CloseCurlybracket if !json5 || close != Token::CloseCurlybracket => {
+                            return Err(JsonError::InvalidComaEndObjectError(separator.span.start));
+                        }
+                        Token::CloseBracket if !json5 && close == Token::CloseBracket => {
+                            return Err(JsonError::ValueError(next.token, next.span.start));
+                        }
+                        _ => (trivia, next),
+                    }
+                } else if separator.token == close {
+                    (String::new(), separator)
+                } else {
+                    return Err(JsonError::EndObjectError(
+                        separator.token,
+                        separator.span.start,
+                    ));
+                };
+                let closing = next.token == close;
+                match frame {
+                    Frame::Array { items, before } => {
+                        items.push(Element {
+                            before: std::mem::take(before),
+                            value: node,
+                            after,
+                        });
+                        if !closing {
+                            *before = trivia;
+                            token = next;
+                            break;
+                        }
+                    }
+                    Frame::Object {
+                        items,
+                        member,
+                        key_start,
+                        ..
+                    } => {
+                        items.push(Member {
+                            value: node,
+                            after_value: after,
+                            ..*std::mem::take(member)
+                        });
+                        if !closing {
+                            let value;
+                            (**member, *key_start, value) =
+                                self.key(trivia, next, items.len() + 1)?;
+                            token = value;
+                            break;
+                        }
+                    }
+                }
+                node = match stack.pop() {
+                    Some(Frame::Array { items, .. }) => {
+                        Node::Array(Container::new(items, trivia, comma))
+                    }
+                    Some(Frame::Object { items, .. }) => {
+                        Node::Object(Container::new(items, trivia, comma))
+                    }
+                    None => unreachable!("a frame was just read"),
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::types::Num;
+
+    const CONFIG: &str = "// service settings\n{\n  \"name\" : \"app\", // inline\n  \"ports\": [ 80,\n    443 ],\n  /* limits */ limits: {},\n}\n";
+
+    fn json5() -> ParserOptions {
+        ParserOptions {
+            json5: true,
+            ..ParserOptions::default()
+        }
+    }
+
+    fn number(value: i64) -> JsonType {
+        JsonType::Num(Num::Integer(value))
+    }
+
+    #[test]
+    fn it_should_write_back_the_exact_input() {
+        for input in [
+            " { \"a\" :[ 1 ,2 ] , \"b\":{ }, \"c\" : [ ] }\n",
+            "[]",
+            "  \"text\"  ",
+            "{\"a\": 1, \"a\": 2}",
+        ] {
+            assert_eq!(input, Document::parse(input).unwrap().to_string());
+        }
+        let document = Document::parse_with_options(CONFIG, json5()).unwrap();
+        assert_eq!(CONFIG, document.to_string());
+    }
+
+    /// Checks that a document fails on each input with the error of the parser
+    fn assert_parser_errors(inputs: &[&str], options: ParserOptions) {
+        for input in inputs {
+            let expected = Parser::new(Tokenizer::with_options(input, options.clone()))
+                .parse_tokens()
+                .unwrap_err();
+            assert_eq!(
+                Err(expected),
+                Document::parse_with_options(input, options.clone()),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_report_parse_errors() {
+        assert_eq!(
+            Err(JsonError::InvalidComaEndObjectError(
+                crate::token::span::Position::new(7, 1, 8)
+            )),
+            Document::parse("{\"a\": 1, }")
+        );
+        assert!(Document::parse(CONFIG).is_err());
+
+        let options = ParserOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            max_object_members: Some(2),
+            max_depth: Some(3),
+            ..ParserOptions::default()
+        };
+        assert_parser_errors(
+            &[
+                "",
+                " ",
+                "[1 2]",
+                "[1,]",
+                "[1,}",
+                "[1}",
+                "{\"a\" 1}",
+                "{1: 2}",
+                "{\"a\": 1]",
+                "{\"a\": [1}, \"a\": 2}",
+                "{\"a\": 1, \"a\": [}",
+                "{\"a\": 1, \"b\": 2, \"c\": 3}",
+                "[[[[]]]]",
+                "[:]",
+                "[nul]",
+                "1 2",
+                "[1",
+            ],
+            options.clone(),
+        );
+        let json5 = ParserOptions {
+            json5: true,
+            ..options
+        };
+        assert_parser_errors(&["[1,}", "{a: 1,]", "{a: 1, a: 2,}"], json5);
+    }
+
+    #[test]
+    fn it_should_set_values_keeping_the_layout() {
+        let mut document = Document::parse_with_options(CONFIG, json5()).unwrap();
+        document.set("/ports/1", number(8443)).unwrap();
+        document
+            .set("/limits", JsonType::Array(vec![number(1), JsonType::Null]))
+            .unwrap();
+
+        assert_eq!(
+            CONFIG
+                .replace("443 ]", "8443 ]")
+                .replace("{},", "[1,null],"),
+            document.to_string()
+        );
+        assert_eq!(
+            Err(PointerError::NotFound("/missing".to_string())),
+            document.set("/missing", JsonType::Null)
+        );
+        assert_eq!(
+            Some(&number(8443)),
+            document.to_json().unwrap().pointer("/ports/1")
+        );
+    }
+
+    #[test]
+    fn it_should_write_non_finite_floats_only_in_json5() {
+        let infinity = JsonType::Num(Num::Float(f64::INFINITY));
+        let mut document = Document::parse("[1]").unwrap();
+
+        assert_eq!(
+            Err(PointerError::InvalidValue(
+                "inf cannot be represented in JSON".to_string()
+            )),
+            document.set("/0", infinity.clone())
+        );
+        assert_eq!(
+            Err(PointerError::InvalidValue(
+                "NaN cannot be represented in JSON".to_string()
+            )),
+            document.insert_at(
+                "/-",
+                JsonType::Array(vec![JsonType::Num(Num::Float(f64::NAN))])
+            )
+        );
+        assert_eq!("[1]", document.to_string());
+
+        let mut document = Document::parse_with_options("[1]", json5()).unwrap();
+        document.set("/0", infinity).unwrap();
+        assert_eq!("[Infinity]", document.to_string());
+    }
+
+    #[test]
+    fn it_should_limit_the_depth_of_documents_and_edits() {
+        let deep = "[".repeat(100_000);
+        for max_depth in [None, Some(100_000)] {
+            let options = ParserOptions {
+                max_depth,
+                ..ParserOptions::default()
+            };
+            assert!(matches!(
+                Document::parse_with_options(&deep, options),
+                Err(JsonError::DepthLimitExceeded(128, _))
+            ));
+        }
+
+        let options = ParserOptions {
+            max_depth: Some(2),
+            ..ParserOptions::default()
+        };
+        let mut document = Document::parse_with_options("[[1]]", options).unwrap();
+        let nested = JsonType::Array(vec![JsonType::Array(Vec::new())]);
+        assert_eq!(
+            Err(PointerError::InvalidValue(
+                "nesting deeper than 2 levels".to_string()
+            )),
+            document.set("/0", nested.clone())
+        );
+        assert_eq!(
+            Err(PointerError::InvalidValue(
+                "nesting deeper than 2 levels".to_string()
+            )),
+            document.insert_at("/0/-", JsonType::Array(Vec::new()))
+        );
+        document.set("", nested).unwrap();
+        assert_eq!("[[]]", document.to_string());
+    }
+
+    #[test]
+    fn it_should_insert_members_and_elements() {
+        let input = "{\n  \"a\": [1, 2],\n  \"b\": {}\n}";
+        let mut document = Document::parse(input).unwrap();
+        document.insert_at("/c", JsonType::Bool(true)).unwrap();
+        document.insert_at("/a/0", number(0)).unwrap();
+        document.insert_at("/a/-", number(3)).unwrap();
+        document.insert_at("/b/x", JsonType::Null).unwrap();
+        document.insert_at("/b/y", JsonType::Null).unwrap();
+
+        assert_eq!(
+            "{\n  \"a\": [0, 1, 2, 3],\n  \"b\": {\"x\":null,\"y\":null},\n  \"c\": true\n}",
+            document.to_string()
+        );
+        assert_eq!(
+            Err(PointerError::InvalidIndex("9".to_string())),
+            document.insert_at("/a/9", JsonType::Null)
+        );
+        assert_eq!(
+            Err(PointerError::NotFound("/c/d".to_string())),
+            document.insert_at("/c/d", JsonType::Null)
+        );
+    }
+
+    #[test]
+    fn it_should_keep_trailing_comments_with_their_item() {
+        let mut document =
+            Document::parse_with_options("{\n  \"a\": 1 // keep with a\n}", json5()).unwrap();
+        document.insert_at("/b", JsonType::Null).unwrap();
+        assert_eq!(
+            "{\n  \"a\": 1, // keep with a\n  \"b\": null\n}",
+            document.to_string()
+        );
+
+        let mut document = Document::parse_with_options("[0, 1 /* one */ ]", json5()).unwrap();
+        document.insert_at("/-", number(2)).unwrap();
+        assert_eq!("[0, 1, /* one */ 2 ]", document.to_string());
+    }
+
+    #[test]
+    fn it_should_remove_members_and_elements() {
+        let mut document = Document::parse_with_options(CONFIG, json5()).unwrap();
+        document.remove_at("/ports/0").unwrap();
+        document.remove_at("/limits").unwrap();
+
+        assert_eq!(
+            "// service settings\n{\n  \"name\" : \"app\", // inline\n  \"ports\": [ 443 ],\n}\n",
+            document.to_string()
+        );
+
+        document.remove_at("/ports/0").unwrap();
+        document.remove_at("/name").unwrap();
+        assert_eq!(
+            "// service settings\n{\n  \"ports\": [ ],\n}\n",
+            document.to_string()
+        );
+        document.remove_at("/ports").unwrap();
+        assert_eq!("// service settings\n{\n}\n", document.to_string());
+        assert_eq!(
+            Err(PointerError::NotFound("/ports".to_string())),
+            document.remove_at("/ports")
+        );
+    }
+
+    #[test]
+    fn it_should_edit_the_member_the_duplicate_keys_policy_keeps() {
+        let input = r#"{"a": 1, "a": 2}"#;
+        let mut last = Document::parse(input).unwrap();
+        last.set("/a", number(3)).unwrap();
+        let options = ParserOptions {
+            duplicate_keys: DuplicateKeys::First,
+            ..ParserOptions::default()
+        };
+        let mut first = Document::parse_with_options(input, options).unwrap();
+        first.set("/a", number(3)).unwrap();
+
+        assert_eq!(r#"{"a": 1, "a": 3}"#, last.to_string());
+        assert_eq!(r#"{"a": 3, "a": 2}"#, first.to_string());
+    }
+}
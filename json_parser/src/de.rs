@@ -0,0 +1,718 @@
+//! Visitor based deserialization
+//!
+//! [`from_str`] reads the tokens of the input and hands them straight to a
+//! [`Visitor`], without building a [`JsonType`](crate::JsonType) tree first.
+//! Strings without escape sequences are handed out as slices of the input, so
+//! `&str` fields can borrow from it.
+//!
+//! # Examples
+//!
+//! ```
+//! use json_parser::de::{self, FromDeserializer};
+//!
+//! #[derive(FromDeserializer)]
+//! struct Person<'a> {
+//!     name: &'a str,
+//!     age: u8,
+//! }
+//!
+//! let input = r#"{"name": "Alice", "age": 30}"#;
+//! let person: Person = de::from_str(input).unwrap();
+//! assert_eq!("Alice", person.name);
+//! assert_eq!(30, person.age);
+//! ```
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+use crate::error::JsonError;
+use crate::options::{DuplicateKeys, ParserOptions};
+use crate::token::tokenizer::{Position, Span, SpannedToken, StrSource, Token, Tokenizer};
+
+mod impls;
+
+pub use impls::IgnoredAny;
+pub use json_parser_derive::FromDeserializer;
+
+/// Types that can be built by driving a [`Deserializer`]
+///
+/// The `'de` lifetime is the one of the input, types borrowing from it like
+/// `&'de str` can only be read from strings without escape sequences.
+pub trait FromDeserializer<'de>: Sized {
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError>;
+}
+
+/// A source of JSON values handing each one to a [`Visitor`]
+pub trait Deserializer<'de> {
+    /// Reads the next value and calls the matching method of the visitor
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError>;
+
+    /// Calls `visit_null` for `null` and `visit_some` with the deserializer for
+    /// any other value
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError>;
+}
+
+/// Builds a value out of the JSON value it is handed
+///
+/// Every method but `expecting` defaults to an error telling what was
+/// expected and what was found.
+pub trait Visitor<'de>: Sized {
+    type Value;
+
+    /// What the visitor expects, as in "a string" or "an object for Person"
+    fn expecting(&self) -> &'static str;
+
+    fn visit_bool(self, value: bool) -> Result<Self::Value, JsonError> {
+        let _ = value;
+        Err(invalid_type(self.expecting(), "a boolean"))
+    }
+
+    fn visit_i64(self, value: i64) -> Result<Self::Value, JsonError> {
+        let _ = value;
+        Err(invalid_type(self.expecting(), "a number"))
+    }
+
+    fn visit_u64(self, value: u64) -> Result<Self::Value, JsonError> {
+        let _ = value;
+        Err(invalid_type(self.expecting(), "a number"))
+    }
+
+    fn visit_f64(self, value: f64) -> Result<Self::Value, JsonError> {
+        let _ = value;
+        Err(invalid_type(self.expecting(), "a number"))
+    }
+
+    /// A number kept as text, see [`NumberOverflow::Decimal`](crate::NumberOverflow)
+    fn visit_decimal(self, value: String) -> Result<Self::Value, JsonError> {
+        let _ = value;
+        Err(invalid_type(self.expecting(), "a number"))
+    }
+
+    /// A string which had to be unescaped, so it cannot borrow from the input
+    fn visit_string(self, value: String) -> Result<Self::Value, JsonError> {
+        let _ = value;
+        Err(invalid_type(self.expecting(), "a string"))
+    }
+
+    /// A string borrowed from the input, forwarded to `visit_string` by default
+    fn visit_borrowed_str(self, value: &'de str) -> Result<Self::Value, JsonError> {
+        self.visit_string(value.to_string())
+    }
+
+    fn visit_null(self) -> Result<Self::Value, JsonError> {
+        Err(invalid_type(self.expecting(), "null"))
+    }
+
+    /// Called by `deserialize_option` for any value other than `null`
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, JsonError> {
+        let _ = deserializer;
+        Err(invalid_type(self.expecting(), "a value"))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, JsonError> {
+        let _ = seq;
+        Err(invalid_type(self.expecting(), "an array"))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, JsonError> {
+        let _ = map;
+        Err(invalid_type(self.expecting(), "an object"))
+    }
+}
+
+/// Gives access to the elements of an array
+pub trait SeqAccess<'de> {
+    /// Reads the next element, `None` once the array ends
+    fn next_element<T: FromDeserializer<'de>>(&mut self) -> Result<Option<T>, JsonError>;
+}
+
+/// Gives access to the members of an object
+pub trait MapAccess<'de> {
+    /// Reads the next key, `None` once the object ends
+    fn next_key(&mut self) -> Result<Option<Cow<'de, str>>, JsonError>;
+
+    /// Reads the value of the key returned by the last `next_key` call
+    fn next_value<T: FromDeserializer<'de>>(&mut self) -> Result<T, JsonError>;
+}
+
+pub(crate) fn invalid_type(expected: &str, got: &str) -> JsonError {
+    JsonError::DeserializationError(format!("expected {}, got {}", expected, got))
+}
+
+/// Deserializes a value from a JSON string without building a `JsonType`
+pub fn from_str<'de, T: FromDeserializer<'de>>(input: &'de str) -> Result<T, JsonError> {
+    from_str_with_options(input, ParserOptions::default())
+}
+
+/// Same as [`from_str`] with custom [`ParserOptions`]
+pub fn from_str_with_options<'de, T: FromDeserializer<'de>>(
+    input: &'de str,
+    options: ParserOptions,
+) -> Result<T, JsonError> {
+    let mut deserializer = StrDeserializer::with_options(input, options);
+    if deserializer.peek_token()?.is_none() {
+        return Err(JsonError::EmptyInput(deserializer.position()));
+    }
+    let value = T::from_deserializer(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// A [`Deserializer`] reading the tokens of a JSON string
+///
+/// The deserializer is used through a mutable reference, every call reading
+/// one value. Visitors recurse into nested values, so the depth stays bounded
+/// by [`ParserOptions::max_depth`] and never exceeds
+/// [`RECURSION_LIMIT`](crate::RECURSION_LIMIT).
+pub struct StrDeserializer<'de> {
+    input: &'de str,
+    tokenizer: Tokenizer<StrSource<'de>>,
+    peeked: Option<SpannedToken>,
+    /// Number of arrays and objects being read
+    depth: usize,
+}
+
+impl<'de> StrDeserializer<'de> {
+    pub fn new(input: &'de str) -> Self {
+        Self::with_options(input, ParserOptions::default())
+    }
+
+    pub fn with_options(input: &'de str, options: ParserOptions) -> Self {
+        let mut tokenizer = Tokenizer::with_options(input, options);
+        tokenizer.borrow_strings();
+        Self {
+            input,
+            tokenizer,
+            peeked: None,
+            depth: 0,
+        }
+    }
+
+    /// Checks that nothing follows the value read so far
+    pub fn end(&mut self) -> Result<(), JsonError> {
+        match self.next_token()? {
+            Some(SpannedToken { token, span }) => Err(JsonError::TrailingToken(
+                self.owned(token, span),
+                span.start,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<SpannedToken>, JsonError> {
+        match self.peeked.take() {
+            Some(token) => Ok(Some(token)),
+            None => self.tokenizer.next().transpose(),
+        }
+    }
+
+    fn peek_token(&mut self) -> Result<Option<&SpannedToken>, JsonError> {
+        if self.peeked.is_none() {
+            self.peeked = self.tokenizer.next().transpose()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    fn position(&self) -> Position {
+        self.tokenizer.position()
+    }
+
+    /// Goes one level deeper, the opening bracket being at `start`
+    fn enter(&mut self, start: Position) -> Result<(), JsonError> {
+        self.depth += 1;
+        let max = self.tokenizer.options().recursion_limit();
+        if self.depth > max {
+            return Err(JsonError::DepthLimitExceeded(max, start));
+        }
+        Ok(())
+    }
+
+    fn expect_token(&mut self) -> Result<SpannedToken, JsonError> {
+        match self.next_token()? {
+            Some(token) => Ok(token),
+            None => Err(JsonError::UnexpectedEndOfJson(self.position())),
+        }
+    }
+
+    /// Borrows the string token from the input when it holds no escape sequence
+    ///
+    /// The tokenizer leaves such strings empty rather than copying them, only
+    /// the strings it had to unescape being owned.
+    fn string(&self, value: String, span: Span) -> Cow<'de, str> {
+        let start = span.start.offset + 1;
+        let end = span.end.offset - 1;
+        let raw = &self.input[start..end];
+        if raw.contains('\\') {
+            Cow::Owned(value)
+        } else {
+            Cow::Borrowed(raw)
+        }
+    }
+
+    /// Fills in the text the tokenizer left out of a string or an identifier,
+    /// for error messages
+    fn owned(&self, token: Token, span: Span) -> Token {
+        match token {
+            Token::Str(value) => Token::Str(self.string(value, span).into_owned()),
+            Token::Identifier(_) => {
+                Token::Identifier(self.input[span.start.offset..span.end.offset].to_string())
+            }
+            token => token,
+        }
+    }
+}
+
+impl<'de> Deserializer<'de> for &mut StrDeserializer<'de> {
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        let SpannedToken { token, span } = self.expect_token()?;
+        match token {
+            Token::Str(value) => match self.string(value, span) {
+                Cow::Borrowed(value) => visitor.visit_borrowed_str(value),
+                Cow::Owned(value) => visitor.visit_string(value),
+            },
+            Token::Int(value) => visitor.visit_i64(value),
+            Token::UInt(value) => visitor.visit_u64(value),
+            Token::Float(value) => visitor.visit_f64(value),
+            Token::Decimal(value) => visitor.visit_decimal(value),
+            Token::Bool(value) => visitor.visit_bool(value),
+            Token::Null => visitor.visit_null(),
+            Token::OpenCurlybracket => {
+                self.enter(span.start)?;
+                let mut access = ObjectAccess::new(self);
+                let value = visitor.visit_map(&mut access)?;
+                access.finish()?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            Token::OpenBracket => {
+                self.enter(span.start)?;
+                let mut access = ArrayAccess::new(self);
+                let value = visitor.visit_seq(&mut access)?;
+                access.finish()?;
+                self.depth -= 1;
+                Ok(value)
+            }
+            token => Err(JsonError::ValueError(self.owned(token, span), span.start)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        match self.peek_token()? {
+            Some(SpannedToken {
+                token: Token::Null, ..
+            }) => {
+                self.next_token()?;
+                visitor.visit_null()
+            }
+            _ => visitor.visit_some(self),
+        }
+    }
+}
+
+/// Reads the elements of an array, the opening bracket being consumed
+struct ArrayAccess<'a, 'de> {
+    deserializer: &'a mut StrDeserializer<'de>,
+    first: bool,
+    done: bool,
+}
+
+impl<'a, 'de> ArrayAccess<'a, 'de> {
+    fn new(deserializer: &'a mut StrDeserializer<'de>) -> Self {
+        Self {
+            deserializer,
+            first: true,
+            done: false,
+        }
+    }
+
+    /// Moves to the next element, returns `false` once the array ends
+    fn has_next_element(&mut self) -> Result<bool, JsonError> {
+        if self.done {
+            return Ok(false);
+        }
+        let next = self.deserializer.expect_token()?;
+        let next = if self.first {
+            self.first = false;
+            next
+        } else {
+            match next.token {
+                Token::Comma => {
+                    let value = self.deserializer.expect_token()?;
+                    if value.token == Token::CloseBracket {
+                        if self.deserializer.tokenizer.options().json5 {
+                            self.done = true;
+                            return Ok(false);
+                        }
+                        return Err(JsonError::ValueError(value.token, value.span.start));
+                    }
+                    value
+                }
+                Token::CloseBracket => {
+                    self.done = true;
+                    return Ok(false);
+                }
+                token => {
+                    let token = self.deserializer.owned(token, next.span);
+                    return Err(JsonError::EndObjectError(token, next.span.start));
+                }
+            }
+        };
+        if next.token == Token::CloseBracket {
+            self.done = true;
+            return Ok(false);
+        }
+        self.deserializer.peeked = Some(next);
+        Ok(true)
+    }
+
+    /// Skips the elements the visitor did not read
+    fn finish(&mut self) -> Result<(), JsonError> {
+        while self.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(())
+    }
+}
+
+impl<'de> SeqAccess<'de> for &mut ArrayAccess<'_, 'de> {
+    fn next_element<T: FromDeserializer<'de>>(&mut self) -> Result<Option<T>, JsonError> {
+        (**self).next_element()
+    }
+}
+
+impl<'de> SeqAccess<'de> for ArrayAccess<'_, 'de> {
+    fn next_element<T: FromDeserializer<'de>>(&mut self) -> Result<Option<T>, JsonError> {
+        if !self.has_next_element()? {
+            return Ok(None);
+        }
+        T::from_deserializer(&mut *self.deserializer).map(Some)
+    }
+}
+
+/// Reads the members of an object, the opening curly bracket being consumed
+///
+/// With [`DuplicateKeys::First`] the members repeating a key are skipped and
+/// with [`DuplicateKeys::Error`] they are an error, otherwise every member is
+/// handed to the visitor which keeps the last one.
+struct ObjectAccess<'a, 'de> {
+    deserializer: &'a mut StrDeserializer<'de>,
+    first: bool,
+    done: bool,
+    seen: HashSet<Cow<'de, str>>,
+    members: usize,
+}
+
+impl<'a, 'de> ObjectAccess<'a, 'de> {
+    fn new(deserializer: &'a mut StrDeserializer<'de>) -> Self {
+        Self {
+            deserializer,
+            first: true,
+            done: false,
+            seen: HashSet::new(),
+            members: 0,
+        }
+    }
+
+    /// Reads the next key, along with where it starts, and the colon after it
+    fn read_key(&mut self) -> Result<Option<(Cow<'de, str>, Position)>, JsonError> {
+        if self.done {
+            return Ok(None);
+        }
+        let mut next = self.deserializer.expect_token()?;
+        if self.first {
+            self.first = false;
+            if next.token == Token::CloseCurlybracket {
+                self.done = true;
+                return Ok(None);
+            }
+        } else {
+            match next.token {
+                Token::Comma => {
+                    let comma = next.span.start;
+                    next = self.deserializer.expect_token()?;
+                    if next.token == Token::CloseCurlybracket {
+                        if self.deserializer.tokenizer.options().json5 {
+                            self.done = true;
+                            return Ok(None);
+                        }
+                        return Err(JsonError::InvalidComaEndObjectError(comma));
+                    }
+                }
+                Token::CloseCurlybracket => {
+                    self.done = true;
+                    return Ok(None);
+                }
+                token => {
+                    let token = self.deserializer.owned(token, next.span);
+                    return Err(JsonError::EndObjectError(token, next.span.start));
+                }
+            }
+        }
+
+        let key = match next.token {
+            Token::Str(key) => self.deserializer.string(key, next.span),
+            Token::Identifier(_) => {
+                let Span { start, end } = next.span;
+                Cow::Borrowed(&self.deserializer.input[start.offset..end.offset])
+            }
+            token => return Err(JsonError::KeyError(token, next.span.start)),
+        };
+        self.expect_colon()?;
+        Ok(Some((key, next.span.start)))
+    }
+
+    fn expect_colon(&mut self) -> Result<(), JsonError> {
+        let SpannedToken { token, span } = self.deserializer.expect_token()?;
+        match token {
+            Token::Colon => Ok(()),
+            token => Err(JsonError::CollonError(
+                self.deserializer.owned(token, span),
+                span.start,
+            )),
+        }
+    }
+
+    /// Skips the members the visitor did not read
+    fn finish(&mut self) -> Result<(), JsonError> {
+        while self.next_key()?.is_some() {
+            self.next_value::<IgnoredAny>()?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> MapAccess<'de> for &mut ObjectAccess<'_, 'de> {
+    fn next_key(&mut self) -> Result<Option<Cow<'de, str>>, JsonError> {
+        (**self).next_key()
+    }
+
+    fn next_value<T: FromDeserializer<'de>>(&mut self) -> Result<T, JsonError> {
+        (**self).next_value()
+    }
+}
+
+impl<'de> MapAccess<'de> for ObjectAccess<'_, 'de> {
+    fn next_key(&mut self) -> Result<Option<Cow<'de, str>>, JsonError> {
+        loop {
+            let Some((key, start)) = self.read_key()? else {
+                return Ok(None);
+            };
+            self.members += 1;
+            if let Some(max) = self.deserializer.tokenizer.options().max_object_members
+                && self.members > max
+            {
+                return Err(JsonError::TooManyMembers(max, start));
+            }
+            match self.deserializer.tokenizer.options().duplicate_keys {
+                DuplicateKeys::Last => return Ok(Some(key)),
+                _ if self.seen.insert(key.clone()) => return Ok(Some(key)),
+                DuplicateKeys::First => {
+                    self.next_value::<IgnoredAny>()?;
+                }
+                DuplicateKeys::Error => {
+                    return Err(JsonError::DuplicateKey(key.into_owned(), start));
+                }
+            }
+        }
+    }
+
+    fn next_value<T: FromDeserializer<'de>>(&mut self) -> Result<T, JsonError> {
+        T::from_deserializer(&mut *self.deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JsonType;
+    use crate::types::Num;
+    use std::collections::HashMap;
+
+    #[test]
+    fn it_should_borrow_unescaped_strings() {
+        let input = r#"["plain", "esc\naped"]"#;
+        let values: Vec<Cow<str>> = from_str(input).unwrap();
+
+        assert!(matches!(values[0], Cow::Borrowed("plain")));
+        assert!(matches!(&values[1], Cow::Owned(value) if value == "esc\naped"));
+    }
+
+    #[test]
+    fn it_should_report_borrowed_strings_in_errors() {
+        let result = from_str::<HashMap<String, u8>>(r#"{"a" "b"}"#);
+
+        assert_eq!(
+            Err(JsonError::CollonError(
+                Token::Str("b".to_string()),
+                Position::new(5, 1, 6)
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_should_reject_borrowing_escaped_string() {
+        let result = from_str::<&str>(r#""a\tb""#);
+
+        assert_eq!(
+            Err(JsonError::DeserializationError(
+                "expected a borrowed string, got a string with escape sequences".to_string()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_should_deserialize_nested_values() {
+        let input = r#"{"a": [1, 2], "b": [], "c": [3]}"#;
+        let map: HashMap<String, Vec<u8>> = from_str(input).unwrap();
+
+        assert_eq!(vec![1, 2], map["a"]);
+        assert!(map["b"].is_empty());
+        assert_eq!(vec![3], map["c"]);
+    }
+
+    #[test]
+    fn it_should_build_json_type() {
+        let json: JsonType = from_str(r#"{"a": [1, null], "b": "x"}"#).unwrap();
+
+        assert_eq!(JsonType::Num(Num::Integer(1)), json["a"][0]);
+        assert_eq!(JsonType::Null, json["a"][1]);
+        assert_eq!(JsonType::Str("x".to_string()), json["b"]);
+    }
+
+    #[test]
+    fn it_should_report_syntax_errors() {
+        assert_eq!(
+            Err(JsonError::InvalidComaEndObjectError(Position::new(7, 1, 8))),
+            from_str::<JsonType>(r#"{"a": 1, }"#)
+        );
+        assert_eq!(
+            Err(JsonError::ValueError(
+                Token::CloseBracket,
+                Position::new(4, 1, 5)
+            )),
+            from_str::<Vec<u8>>("[1, ]")
+        );
+        assert_eq!(
+            Err(JsonError::TrailingToken(
+                Token::Int(2),
+                Position::new(2, 1, 3)
+            )),
+            from_str::<u8>("1 2")
+        );
+        assert_eq!(
+            Err(JsonError::EmptyInput(Position::new(1, 1, 2))),
+            from_str::<u8>(" ")
+        );
+    }
+
+    #[test]
+    fn it_should_skip_unread_members() {
+        let input = r#"{"a": {"deep": [1, {"x": null}]}, "b": 2}"#;
+        let map: HashMap<String, IgnoredAny> = from_str(input).unwrap();
+
+        assert_eq!(2, map.len());
+    }
+
+    #[test]
+    fn it_should_apply_the_duplicate_keys_policy() {
+        let json = r#"{"a": 1, "b": 2, "a": 3}"#;
+        let first = ParserOptions {
+            duplicate_keys: DuplicateKeys::First,
+            ..ParserOptions::default()
+        };
+
+        let map: HashMap<String, u8> = from_str_with_options(json, first.clone()).unwrap();
+        let value: JsonType = from_str_with_options(json, first).unwrap();
+        assert_eq!(Some(&1), map.get("a"));
+        assert_eq!(JsonType::Num(Num::Integer(1)), value["a"]);
+
+        let map: HashMap<String, u8> = from_str(json).unwrap();
+        let value: JsonType = from_str(json).unwrap();
+        assert_eq!(Some(&3), map.get("a"));
+        assert_eq!(JsonType::Num(Num::Integer(3)), value["a"]);
+    }
+
+    #[test]
+    fn it_should_reject_duplicate_keys() {
+        let options = ParserOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            ..ParserOptions::default()
+        };
+
+        let result: Result<HashMap<String, u8>, _> =
+            from_str_with_options(r#"{"a": 1, "a": 1}"#, options.clone());
+        assert_eq!(
+            Err(JsonError::DuplicateKey(
+                "a".to_string(),
+                Position::new(9, 1, 10)
+            )),
+            result
+        );
+
+        let result: Result<JsonType, _> = from_str_with_options(r#"[{"a": 1}, {"a": 2}]"#, options);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn it_should_apply_the_limits() {
+        let options = ParserOptions {
+            max_depth: Some(2),
+            max_object_members: Some(1),
+            ..ParserOptions::default()
+        };
+
+        let result: Result<Vec<Vec<Vec<u8>>>, _> = from_str_with_options("[[[]]]", options.clone());
+        assert_eq!(
+            Err(JsonError::DepthLimitExceeded(2, Position::new(2, 1, 3))),
+            result
+        );
+
+        let result: Result<HashMap<String, u8>, _> =
+            from_str_with_options(r#"{"a": 1, "b": 2}"#, options.clone());
+        assert_eq!(
+            Err(JsonError::TooManyMembers(1, Position::new(9, 1, 10))),
+            result
+        );
+
+        let value: JsonType = from_str_with_options(r#"[{"a": 1}, {"b": 2}]"#, options).unwrap();
+        assert_eq!(JsonType::Num(Num::Integer(2)), value[1]["b"]);
+    }
+
+    #[test]
+    fn it_should_stop_at_the_recursion_limit() {
+        let deep = "[".repeat(100_000);
+        for max_depth in [None, Some(100_000)] {
+            let options = ParserOptions {
+                max_depth,
+                ..ParserOptions::default()
+            };
+
+            let result: Result<JsonType, _> = from_str_with_options(&deep, options);
+            assert_eq!(
+                Err(JsonError::DepthLimitExceeded(
+                    128,
+                    Position::new(128, 1, 129)
+                )),
+                result
+            );
+        }
+
+        let nested = format!("{}{}", "[".repeat(128), "]".repeat(128));
+        assert!(from_str::<JsonType>(&nested).is_ok());
+    }
+
+    #[test]
+    fn it_should_deserialize_json5() {
+        let options = ParserOptions {
+            json5: true,
+            ..ParserOptions::default()
+        };
+        let input = "{key: [1, 2,], 'other': [], /* done */}";
+        let map: HashMap<String, Vec<u8>> = from_str_with_options(input, options.clone()).unwrap();
+
+        assert_eq!(Some(&vec![1, 2]), map.get("key"));
+        assert_eq!(Some(&vec![]), map.get("other"));
+        assert!(from_str::<HashMap<String, Vec<u8>>>(input).is_err());
+        assert!(from_str_with_options::<Vec<u8>>("[1,,]", options).is_err());
+    }
+}
@@ -0,0 +1,475 @@
+//! `FromDeserializer` implementations for `JsonType` and standard types
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, Hash};
+use std::marker::PhantomData;
+
+use super::{Deserializer, FromDeserializer, MapAccess, SeqAccess, Visitor, invalid_type};
+use crate::JsonType;
+use crate::error::JsonError;
+use crate::impls::out_of_range;
+use crate::types::{Num, Object};
+
+/// Reads and discards any value, used to skip the members nobody asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct IgnoredAny;
+
+impl<'de> FromDeserializer<'de> for IgnoredAny {
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        deserializer.deserialize_any(IgnoredAny)
+    }
+}
+
+impl<'de> Visitor<'de> for IgnoredAny {
+    type Value = IgnoredAny;
+
+    fn expecting(&self) -> &'static str {
+        "any value"
+    }
+
+    fn visit_bool(self, _: bool) -> Result<Self::Value, JsonError> {
+        Ok(IgnoredAny)
+    }
+
+    fn visit_i64(self, _: i64) -> Result<Self::Value, JsonError> {
+        Ok(IgnoredAny)
+    }
+
+    fn visit_u64(self, _: u64) -> Result<Self::Value, JsonError> {
+        Ok(IgnoredAny)
+    }
+
+    fn visit_f64(self, _: f64) -> Result<Self::Value, JsonError> {
+        Ok(IgnoredAny)
+    }
+
+    fn visit_decimal(self, _: String) -> Result<Self::Value, JsonError> {
+        Ok(IgnoredAny)
+    }
+
+    fn visit_string(self, _: String) -> Result<Self::Value, JsonError> {
+        Ok(IgnoredAny)
+    }
+
+    fn visit_borrowed_str(self, _: &'de str) -> Result<Self::Value, JsonError> {
+        Ok(IgnoredAny)
+    }
+
+    fn visit_null(self) -> Result<Self::Value, JsonError> {
+        Ok(IgnoredAny)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, JsonError> {
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok(IgnoredAny)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, JsonError> {
+        while map.next_key()?.is_some() {
+            map.next_value::<IgnoredAny>()?;
+        }
+        Ok(IgnoredAny)
+    }
+}
+
+struct JsonTypeVisitor;
+
+impl<'de> FromDeserializer<'de> for JsonType {
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        deserializer.deserialize_any(JsonTypeVisitor)
+    }
+}
+
+impl<'de> Visitor<'de> for JsonTypeVisitor {
+    type Value = JsonType;
+
+    fn expecting(&self) -> &'static str {
+        "any value"
+    }
+
+    fn visit_bool(self, value: bool) -> Result<Self::Value, JsonError> {
+        Ok(JsonType::Bool(value))
+    }
+
+    fn visit_i64(self, value: i64) -> Result<Self::Value, JsonError> {
+        Ok(JsonType::Num(Num::Integer(value)))
+    }
+
+    fn visit_u64(self, value: u64) -> Result<Self::Value, JsonError> {
+        Ok(JsonType::Num(Num::UInteger(value)))
+    }
+
+    fn visit_f64(self, value: f64) -> Result<Self::Value, JsonError> {
+        Ok(JsonType::Num(Num::Float(value)))
+    }
+
+    fn visit_decimal(self, value: String) -> Result<Self::Value, JsonError> {
+        Ok(JsonType::Num(Num::Decimal(value)))
+    }
+
+    fn visit_string(self, value: String) -> Result<Self::Value, JsonError> {
+        Ok(JsonType::Str(value))
+    }
+
+    fn visit_null(self) -> Result<Self::Value, JsonError> {
+        Ok(JsonType::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, JsonError> {
+        let mut array = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            array.push(value);
+        }
+        Ok(JsonType::Array(array))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, JsonError> {
+        let mut data = Vec::new();
+        while let Some(key) = map.next_key()? {
+            data.push((key.into_owned(), map.next_value()?));
+        }
+        Ok(JsonType::Object(Box::new(Object::new(data))))
+    }
+}
+
+struct BoolVisitor;
+
+impl<'de> FromDeserializer<'de> for bool {
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        deserializer.deserialize_any(BoolVisitor)
+    }
+}
+
+impl<'de> Visitor<'de> for BoolVisitor {
+    type Value = bool;
+
+    fn expecting(&self) -> &'static str {
+        "a boolean"
+    }
+
+    fn visit_bool(self, value: bool) -> Result<Self::Value, JsonError> {
+        Ok(value)
+    }
+}
+
+struct StringVisitor;
+
+impl<'de> FromDeserializer<'de> for String {
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        deserializer.deserialize_any(StringVisitor)
+    }
+}
+
+impl<'de> Visitor<'de> for StringVisitor {
+    type Value = String;
+
+    fn expecting(&self) -> &'static str {
+        "a string"
+    }
+
+    fn visit_string(self, value: String) -> Result<Self::Value, JsonError> {
+        Ok(value)
+    }
+}
+
+struct BorrowedStrVisitor;
+
+impl<'de: 'a, 'a> FromDeserializer<'de> for &'a str {
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        deserializer.deserialize_any(BorrowedStrVisitor)
+    }
+}
+
+impl<'de> Visitor<'de> for BorrowedStrVisitor {
+    type Value = &'de str;
+
+    fn expecting(&self) -> &'static str {
+        "a borrowed string"
+    }
+
+    fn visit_string(self, _: String) -> Result<Self::Value, JsonError> {
+        Err(invalid_type(
+            self.expecting(),
+            "a string with escape sequences",
+        ))
+    }
+
+    fn visit_borrowed_str(self, value: &'de str) -> Result<Self::Value, JsonError> {
+        Ok(value)
+    }
+}
+
+struct CowStrVisitor;
+
+impl<'de: 'a, 'a> FromDeserializer<'de> for Cow<'a, str> {
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        deserializer.deserialize_any(CowStrVisitor)
+    }
+}
+
+impl<'de> Visitor<'de> for CowStrVisitor {
+    type Value = Cow<'de, str>;
+
+    fn expecting(&self) -> &'static str {
+        "a string"
+    }
+
+    fn visit_string(self, value: String) -> Result<Self::Value, JsonError> {
+        Ok(Cow::Owned(value))
+    }
+
+    fn visit_borrowed_str(self, value: &'de str) -> Result<Self::Value, JsonError> {
+        Ok(Cow::Borrowed(value))
+    }
+}
+
+struct UnitVisitor;
+
+impl<'de> FromDeserializer<'de> for () {
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        deserializer.deserialize_any(UnitVisitor)
+    }
+}
+
+impl<'de> Visitor<'de> for UnitVisitor {
+    type Value = ();
+
+    fn expecting(&self) -> &'static str {
+        "null"
+    }
+
+    fn visit_null(self) -> Result<Self::Value, JsonError> {
+        Ok(())
+    }
+}
+
+/// Reads an integer type, checking the value fits in it
+struct IntegerVisitor<T> {
+    expecting: &'static str,
+    type_name: &'static str,
+    marker: PhantomData<T>,
+}
+
+impl<T> IntegerVisitor<T> {
+    fn not_an_integer(&self, value: &dyn std::fmt::Display) -> JsonError {
+        JsonError::DeserializationError(format!(
+            "expected an integer for {}, got {}",
+            self.type_name, value
+        ))
+    }
+}
+
+impl<'de, T> Visitor<'de> for IntegerVisitor<T>
+where
+    T: TryFrom<i64> + TryFrom<u64> + std::str::FromStr,
+{
+    type Value = T;
+
+    fn expecting(&self) -> &'static str {
+        self.expecting
+    }
+
+    fn visit_i64(self, value: i64) -> Result<Self::Value, JsonError> {
+        T::try_from(value).map_err(|_| out_of_range(&value, self.type_name))
+    }
+
+    fn visit_u64(self, value: u64) -> Result<Self::Value, JsonError> {
+        T::try_from(value).map_err(|_| out_of_range(&value, self.type_name))
+    }
+
+    fn visit_f64(self, value: f64) -> Result<Self::Value, JsonError> {
+        // `-0` is read as a float, which is still the integer zero
+        if value == 0.0 && value.is_sign_negative() {
+            return T::try_from(0i64).map_err(|_| out_of_range(&value, self.type_name));
+        }
+        Err(self.not_an_integer(&value))
+    }
+
+    fn visit_decimal(self, value: String) -> Result<Self::Value, JsonError> {
+        match value.parse() {
+            Ok(parsed) => Ok(parsed),
+            Err(_) if value.contains(['.', 'e', 'E']) => Err(self.not_an_integer(&value)),
+            Err(_) => Err(out_of_range(&value, self.type_name)),
+        }
+    }
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl<'de> FromDeserializer<'de> for $ty {
+                fn from_deserializer<D: Deserializer<'de>>(
+                    deserializer: D,
+                ) -> Result<Self, JsonError> {
+                    deserializer.deserialize_any(IntegerVisitor {
+                        expecting: concat!("an integer for ", stringify!($ty)),
+                        type_name: stringify!($ty),
+                        marker: PhantomData,
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(
+    i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+);
+
+/// Reads any number as an `f64`
+struct FloatVisitor {
+    expecting: &'static str,
+    type_name: &'static str,
+}
+
+impl<'de> Visitor<'de> for FloatVisitor {
+    type Value = f64;
+
+    fn expecting(&self) -> &'static str {
+        self.expecting
+    }
+
+    fn visit_i64(self, value: i64) -> Result<Self::Value, JsonError> {
+        Ok(value as f64)
+    }
+
+    fn visit_u64(self, value: u64) -> Result<Self::Value, JsonError> {
+        Ok(value as f64)
+    }
+
+    fn visit_f64(self, value: f64) -> Result<Self::Value, JsonError> {
+        Ok(value)
+    }
+
+    fn visit_decimal(self, value: String) -> Result<Self::Value, JsonError> {
+        match value.parse::<f64>() {
+            Ok(parsed) if parsed.is_finite() => Ok(parsed),
+            _ => Err(out_of_range(&value, self.type_name)),
+        }
+    }
+}
+
+impl<'de> FromDeserializer<'de> for f64 {
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        deserializer.deserialize_any(FloatVisitor {
+            expecting: "a number for f64",
+            type_name: "f64",
+        })
+    }
+}
+
+impl<'de> FromDeserializer<'de> for f32 {
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        let value = deserializer.deserialize_any(FloatVisitor {
+            expecting: "a number for f32",
+            type_name: "f32",
+        })?;
+        let narrowed = value as f32;
+        if narrowed.is_finite() {
+            Ok(narrowed)
+        } else {
+            Err(out_of_range(&value, "f32"))
+        }
+    }
+}
+
+struct OptionVisitor<T>(PhantomData<T>);
+
+impl<'de, T: FromDeserializer<'de>> FromDeserializer<'de> for Option<T> {
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        deserializer.deserialize_option(OptionVisitor(PhantomData))
+    }
+}
+
+impl<'de, T: FromDeserializer<'de>> Visitor<'de> for OptionVisitor<T> {
+    type Value = Option<T>;
+
+    fn expecting(&self) -> &'static str {
+        "an optional value"
+    }
+
+    fn visit_null(self) -> Result<Self::Value, JsonError> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, JsonError> {
+        T::from_deserializer(deserializer).map(Some)
+    }
+}
+
+impl<'de, T: FromDeserializer<'de>> FromDeserializer<'de> for Box<T> {
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        T::from_deserializer(deserializer).map(Box::new)
+    }
+}
+
+struct VecVisitor<T>(PhantomData<T>);
+
+impl<'de, T: FromDeserializer<'de>> FromDeserializer<'de> for Vec<T> {
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        deserializer.deserialize_any(VecVisitor(PhantomData))
+    }
+}
+
+impl<'de, T: FromDeserializer<'de>> Visitor<'de> for VecVisitor<T> {
+    type Value = Vec<T>;
+
+    fn expecting(&self) -> &'static str {
+        "an array"
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, JsonError> {
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(values)
+    }
+}
+
+/// Reads an object into any map with string keys
+struct MapVisitor<K, V, M>(PhantomData<(K, V, M)>);
+
+impl<'de, K, V, M> Visitor<'de> for MapVisitor<K, V, M>
+where
+    K: From<String>,
+    V: FromDeserializer<'de>,
+    M: Extend<(K, V)> + Default,
+{
+    type Value = M;
+
+    fn expecting(&self) -> &'static str {
+        "an object"
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, JsonError> {
+        let mut values = M::default();
+        while let Some(key) = map.next_key()? {
+            let value = map.next_value()?;
+            values.extend([(K::from(key.into_owned()), value)]);
+        }
+        Ok(values)
+    }
+}
+
+impl<'de, K, V, S> FromDeserializer<'de> for HashMap<K, V, S>
+where
+    K: From<String> + Eq + Hash,
+    V: FromDeserializer<'de>,
+    S: BuildHasher + Default,
+{
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        deserializer.deserialize_any(MapVisitor(PhantomData))
+    }
+}
+
+impl<'de, K, V> FromDeserializer<'de> for BTreeMap<K, V>
+where
+    K: From<String> + Ord,
+    V: FromDeserializer<'de>,
+{
+    fn from_deserializer<D: Deserializer<'de>>(deserializer: D) -> Result<Self, JsonError> {
+        deserializer.deserialize_any(MapVisitor(PhantomData))
+    }
+}
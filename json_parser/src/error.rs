@@ -1,45 +1,233 @@
+use crate::token::span::Position;
 use crate::token::tokenizer::Token;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
+/// Errors returned while tokenizing, parsing, serializing or deserializing
+///
+/// Every error raised while reading the input carries the [`Position`] where
+/// it happened.
 #[derive(Debug, PartialEq)]
 pub enum JsonError {
-    EmptyInput,
-    UnexpectedToken(Token),
-    KeyError(Token),
-    ValueError(Token),
-    CollonError(Token),
-    ComaError(Token),
-    EndObjectError(Token),
-    InvalidComaEndObjectError,
-    UnexpectedEndOfJson,
+    EmptyInput(Position),
+    UnexpectedToken(Token, Position),
+    TrailingToken(Token, Position),
+    KeyError(Token, Position),
+    ValueError(Token, Position),
+    CollonError(Token, Position),
+    ComaError(Token, Position),
+    EndObjectError(Token, Position),
+    InvalidComaEndObjectError(Position),
+    UnexpectedEndOfJson(Position),
+    UnterminatedString(Position),
+    InvalidEscape(char, Position),
+    InvalidUnicodeEscape(String, Position),
+    LoneSurrogate(u16, Position),
+    ControlCharacter(char, Position),
+    InvalidNumber(String, Position),
+    NumberOverflow(String, Position),
+    InvalidLiteral(String, Position),
+    UnexpectedCharacter(char, Position),
+    InvalidUtf8(Position),
+    DuplicateKey(String, Position),
+    DepthLimitExceeded(usize, Position),
+    StringTooLong(usize, Position),
+    TooManyMembers(usize, Position),
+    InputTooLarge(Position),
+    UnterminatedComment(Position),
     DeserializationError(String),
     SerializationError(String),
+    IoError(String),
 }
 
-impl Error for JsonError {}
+/// Matches `$error` on the variants carrying a position, bound to `$position`
+/// in `$found`, every other variant giving `$none`
+macro_rules! match_position {
+    ($error:expr, $position:ident => $found:expr, $none:expr) => {
+        match $error {
+            JsonError::EmptyInput($position)
+            | JsonError::UnexpectedToken(_, $position)
+            | JsonError::TrailingToken(_, $position)
+            | JsonError::KeyError(_, $position)
+            | JsonError::ValueError(_, $position)
+            | JsonError::CollonError(_, $position)
+            | JsonError::ComaError(_, $position)
+            | JsonError::EndObjectError(_, $position)
+            | JsonError::InvalidComaEndObjectError($position)
+            | JsonError::UnexpectedEndOfJson($position)
+            | JsonError::UnterminatedString($position)
+            | JsonError::InvalidEscape(_, $position)
+            | JsonError::InvalidUnicodeEscape(_, $position)
+            | JsonError::LoneSurrogate(_, $position)
+            | JsonError::ControlCharacter(_, $position)
+            | JsonError::InvalidNumber(_, $position)
+            | JsonError::NumberOverflow(_, $position)
+            | JsonError::InvalidLiteral(_, $position)
+            | JsonError::UnexpectedCharacter(_, $position)
+            | JsonError::InvalidUtf8($position)
+            | JsonError::DuplicateKey(_, $position)
+            | JsonError::DepthLimitExceeded(_, $position)
+            | JsonError::StringTooLong(_, $position)
+            | JsonError::TooManyMembers(_, $position)
+            | JsonError::InputTooLarge($position)
+            | JsonError::UnterminatedComment($position) => $found,
+            JsonError::DeserializationError(_)
+            | JsonError::SerializationError(_)
+            | JsonError::IoError(_) => $none,
+        }
+    };
+}
 
-impl Display for JsonError {
-    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
-        let msg = match self {
+impl JsonError {
+    /// Returns where in the input the error happened, if it comes from the input
+    pub fn position(&self) -> Option<Position> {
+        match_position!(self, position => Some(*position), None)
+    }
+
+    /// Returns the position of the error for it to be moved, if it has one
+    pub(crate) fn position_mut(&mut self) -> Option<&mut Position> {
+        match_position!(self, position => Some(position), None)
+    }
+
+    /// Pairs the error with the input it was raised on
+    ///
+    /// The returned value displays the error followed by the offending line
+    /// of `source` with a caret under the error position.
+    pub fn with_source<'a>(&'a self, source: &'a str) -> SourceError<'a> {
+        SourceError {
+            error: self,
+            source,
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
             JsonError::SerializationError(msg) => {
                 format!("Deserialization into struct error: {}.", msg)
             }
             JsonError::DeserializationError(msg) => {
                 format!("Deserialization into struct error: {}.", msg)
             }
-            JsonError::InvalidComaEndObjectError => format!("An object must not end with a coma."),
-            JsonError::UnexpectedEndOfJson => format!("Unexpected end of json string."),
-            JsonError::EmptyInput => format!("Empty input string"),
-            JsonError::UnexpectedToken(got) => format!("Unexpected token, got: {}", got),
-            JsonError::KeyError(token) => format!("Key error, got: {}", token),
-            JsonError::ValueError(token) => format!("Value error, got: {}", token),
-            JsonError::CollonError(token) => format!("Expected collon ':' but got: {}", token),
-            JsonError::ComaError(token) => format!("Expected coma ',' but got: {}", token),
-            JsonError::EndObjectError(token) => {
+            JsonError::IoError(msg) => format!("I/O error: {}", msg),
+            JsonError::InvalidComaEndObjectError(_) => {
+                "An object must not end with a coma.".to_string()
+            }
+            JsonError::UnexpectedEndOfJson(_) => "Unexpected end of json string.".to_string(),
+            JsonError::UnterminatedString(_) => "String does not end with '\"'.".to_string(),
+            JsonError::InvalidEscape(escaped, _) => {
+                format!("Invalid escape sequence: \\{}", escaped)
+            }
+            JsonError::InvalidUnicodeEscape(hex, _) => {
+                format!("Invalid unicode escape sequence: \\u{}", hex)
+            }
+            JsonError::LoneSurrogate(code, _) => {
+                format!("Unpaired UTF-16 surrogate: \\u{:04X}", code)
+            }
+            JsonError::ControlCharacter(got, _) => {
+                format!("Unescaped control character in string: {:?}", got)
+            }
+            JsonError::InvalidNumber(number, _) => format!("Invalid number: {}", number),
+            JsonError::NumberOverflow(number, _) => format!("Number out of range: {}", number),
+            JsonError::InvalidLiteral(literal, _) => format!("Invalid literal: {}", literal),
+            JsonError::UnexpectedCharacter(got, _) => format!("Unexpected character: {}", got),
+            JsonError::InvalidUtf8(_) => "Invalid UTF-8 in input".to_string(),
+            JsonError::DuplicateKey(key, _) => format!("Duplicate key in object: {:?}", key),
+            JsonError::DepthLimitExceeded(max, _) => {
+                format!("Nesting deeper than {} arrays and objects", max)
+            }
+            JsonError::StringTooLong(max, _) => format!("String longer than {} bytes", max),
+            JsonError::TooManyMembers(max, _) => format!("Object with more than {} members", max),
+            JsonError::InputTooLarge(_) => "Input larger than the maximum size".to_string(),
+            JsonError::UnterminatedComment(_) => "Unterminated comment".to_string(),
+            JsonError::EmptyInput(_) => "Empty input string".to_string(),
+            JsonError::UnexpectedToken(got, _) => format!("Unexpected token, got: {}", got),
+            JsonError::TrailingToken(got, _) => {
+                format!(
+                    "Unexpected token after the end of the document, got: {}",
+                    got
+                )
+            }
+            JsonError::KeyError(token, _) => format!("Key error, got: {}", token),
+            JsonError::ValueError(token, _) => format!("Value error, got: {}", token),
+            JsonError::CollonError(token, _) => format!("Expected collon ':' but got: {}", token),
+            JsonError::ComaError(token, _) => format!("Expected coma ',' but got: {}", token),
+            JsonError::EndObjectError(token, _) => {
                 format!("Object does not end properly, got: {}", token)
             }
+        }
+    }
+}
+
+impl Error for JsonError {}
+
+impl From<std::io::Error> for JsonError {
+    fn from(error: std::io::Error) -> Self {
+        JsonError::IoError(error.to_string())
+    }
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self.position() {
+            Some(position) => write!(f, "Error: {} at {}", self.message(), position),
+            None => write!(f, "Error: {}", self.message()),
+        }
+    }
+}
+
+/// A [`JsonError`] displayed along with an excerpt of the input
+///
+/// ```text
+/// Error: Unexpected character: @ at line 2, column 10
+///   "key": @
+///          ^
+/// ```
+pub struct SourceError<'a> {
+    error: &'a JsonError,
+    source: &'a str,
+}
+
+impl Display for SourceError<'_> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.error)?;
+        let Some(position) = self.error.position() else {
+            return Ok(());
+        };
+        let Some(line) = self.source.lines().nth(position.line - 1) else {
+            return Ok(());
         };
-        write!(f, "Error: {}", msg)
+        // Keep tabs so the caret lines up with the excerpt
+        let padding: String = line
+            .chars()
+            .take(position.column - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        write!(f, "\n{}\n{}^", line, padding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_should_display_the_error_position() {
+        let error = JsonError::UnexpectedCharacter('@', Position::new(12, 2, 10));
+
+        assert_eq!(
+            "Error: Unexpected character: @ at line 2, column 10",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn it_should_display_an_excerpt_of_the_source() {
+        let source = "{\n  \"key\": @\n}";
+        let error = JsonError::UnexpectedCharacter('@', Position::new(11, 2, 10));
+
+        assert_eq!(
+            "Error: Unexpected character: @ at line 2, column 10\n  \"key\": @\n         ^",
+            error.with_source(source).to_string()
+        );
     }
 }
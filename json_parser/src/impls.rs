@@ -0,0 +1,724 @@
+//! `Serialize` and `Deserialize` implementations for standard types
+//!
+//! Integers are range checked when read from a [`Num`], maps use string keys
+//! and sets are stored as arrays. `&str` and other references can only be
+//! serialized, as deserializing produces owned values.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::hash::{BuildHasher, Hash};
+
+use crate::error::JsonError;
+use crate::private::describe;
+use crate::serializer::{SerializeMap, SerializeSeq, Serializer, serialize_num};
+use crate::types::{Num, Object};
+use crate::{Deserialize, JsonType, Serialize};
+
+fn invalid_type(expected: &str, got: &JsonType) -> JsonError {
+    JsonError::DeserializationError(format!("expected {}, got {}", expected, describe(got)))
+}
+
+pub(crate) fn out_of_range(value: &dyn std::fmt::Display, type_name: &str) -> JsonError {
+    JsonError::DeserializationError(format!("{} is out of range for {}", value, type_name))
+}
+
+fn invalid_length(expected: usize, got: usize) -> JsonError {
+    JsonError::DeserializationError(format!(
+        "expected an array of {} elements, got {}",
+        expected, got
+    ))
+}
+
+// A missing value is represented by `null`, so `None` serializes to
+// `JsonType::Null` and `JsonType::Null` deserializes back to `None`.
+impl<T: Deserialize> Deserialize for Option<T> {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        match data {
+            JsonType::Null => Ok(None),
+            data => Ok(Some(T::deserialize(data)?)),
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for Option<T> {
+    fn serialize(&self) -> JsonType {
+        match self {
+            Some(value) => value.serialize(),
+            None => JsonType::Null,
+        }
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        match self {
+            Some(value) => value.serialize_into(serializer),
+            None => serializer.serialize_null(),
+        }
+    }
+}
+
+impl<T: Serialize + ?Sized> Serialize for &T {
+    fn serialize(&self) -> JsonType {
+        (**self).serialize()
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        (**self).serialize_into(serializer)
+    }
+}
+
+impl<T: Serialize + ?Sized> Serialize for &mut T {
+    fn serialize(&self) -> JsonType {
+        (**self).serialize()
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        (**self).serialize_into(serializer)
+    }
+}
+
+impl<T: Serialize + ?Sized> Serialize for Box<T> {
+    fn serialize(&self) -> JsonType {
+        (**self).serialize()
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        (**self).serialize_into(serializer)
+    }
+}
+
+impl<T: Deserialize> Deserialize for Box<T> {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        T::deserialize(data).map(Box::new)
+    }
+}
+
+impl Serialize for bool {
+    fn serialize(&self) -> JsonType {
+        JsonType::Bool(*self)
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serializer.serialize_bool(*self)
+    }
+}
+
+impl Deserialize for bool {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        match data {
+            JsonType::Bool(value) => Ok(value),
+            other => Err(invalid_type("a boolean", &other)),
+        }
+    }
+}
+
+impl Serialize for str {
+    fn serialize(&self) -> JsonType {
+        JsonType::Str(self.to_string())
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serializer.serialize_str(self)
+    }
+}
+
+impl Serialize for String {
+    fn serialize(&self) -> JsonType {
+        JsonType::Str(self.clone())
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serializer.serialize_str(self)
+    }
+}
+
+impl Deserialize for String {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        match data {
+            JsonType::Str(value) => Ok(value),
+            other => Err(invalid_type("a string", &other)),
+        }
+    }
+}
+
+impl Serialize for char {
+    fn serialize(&self) -> JsonType {
+        JsonType::Str(self.to_string())
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serializer.serialize_str(self.encode_utf8(&mut [0; 4]))
+    }
+}
+
+impl Deserialize for char {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        let value = String::deserialize(data)?;
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(JsonError::DeserializationError(format!(
+                "expected a single character, got \"{}\"",
+                value
+            ))),
+        }
+    }
+}
+
+impl Serialize for () {
+    fn serialize(&self) -> JsonType {
+        JsonType::Null
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serializer.serialize_null()
+    }
+}
+
+impl Deserialize for () {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        match data {
+            JsonType::Null => Ok(()),
+            other => Err(invalid_type("null", &other)),
+        }
+    }
+}
+
+/// Builds the `Num` of an integer, `Decimal` being used beyond `u64`
+fn integer_to_num(value: i128) -> Num {
+    if let Ok(value) = i64::try_from(value) {
+        Num::Integer(value)
+    } else if let Ok(value) = u64::try_from(value) {
+        Num::UInteger(value)
+    } else {
+        Num::Decimal(value.to_string())
+    }
+}
+
+/// Reads an integer type out of any `Num` holding an integer in its range
+fn num_to_integer<T>(data: JsonType, type_name: &str) -> Result<T, JsonError>
+where
+    T: TryFrom<i64> + TryFrom<u64> + std::str::FromStr,
+{
+    let num = match data {
+        JsonType::Num(num) => num,
+        other => {
+            return Err(invalid_type(
+                &format!("an integer for {}", type_name),
+                &other,
+            ));
+        }
+    };
+    match num {
+        Num::Integer(value) => T::try_from(value).map_err(|_| out_of_range(&value, type_name)),
+        Num::UInteger(value) => T::try_from(value).map_err(|_| out_of_range(&value, type_name)),
+        Num::Decimal(value) => match value.parse() {
+            Ok(parsed) => Ok(parsed),
+            Err(_) if value.contains(['.', 'e', 'E']) => Err(JsonError::DeserializationError(
+                format!("expected an integer for {}, got {}", type_name, value),
+            )),
+            Err(_) => Err(out_of_range(&value, type_name)),
+        },
+        // `-0` is read as a float, which is still the integer zero
+        Num::Float(value) if value == 0.0 && value.is_sign_negative() => {
+            T::try_from(0i64).map_err(|_| out_of_range(&value, type_name))
+        }
+        Num::Float(value) => Err(JsonError::DeserializationError(format!(
+            "expected an integer for {}, got {}",
+            type_name, value
+        ))),
+    }
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl Serialize for $ty {
+                fn serialize(&self) -> JsonType {
+                    JsonType::Num(integer_to_num(*self as i128))
+                }
+
+                fn serialize_into<S: Serializer + ?Sized>(
+                    &self,
+                    serializer: &mut S,
+                ) -> Result<(), JsonError> {
+                    serialize_num(&integer_to_num(*self as i128), serializer)
+                }
+            }
+
+            impl Deserialize for $ty {
+                fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+                    num_to_integer(data, stringify!($ty))
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, usize);
+
+impl Serialize for u128 {
+    fn serialize(&self) -> JsonType {
+        match i128::try_from(*self) {
+            Ok(value) => JsonType::Num(integer_to_num(value)),
+            Err(_) => JsonType::Num(Num::Decimal(self.to_string())),
+        }
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        match i128::try_from(*self) {
+            Ok(value) => serialize_num(&integer_to_num(value), serializer),
+            Err(_) => serializer.serialize_decimal(&self.to_string()),
+        }
+    }
+}
+
+impl Deserialize for u128 {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        num_to_integer(data, "u128")
+    }
+}
+
+/// Reads any `Num` as an `f64`
+fn num_to_float(data: JsonType, type_name: &str) -> Result<f64, JsonError> {
+    match data {
+        JsonType::Num(Num::Integer(value)) => Ok(value as f64),
+        JsonType::Num(Num::UInteger(value)) => Ok(value as f64),
+        JsonType::Num(Num::Float(value)) => Ok(value),
+        JsonType::Num(Num::Decimal(value)) => match value.parse::<f64>() {
+            Ok(parsed) if parsed.is_finite() => Ok(parsed),
+            _ => Err(out_of_range(&value, type_name)),
+        },
+        other => Err(invalid_type(&format!("a number for {}", type_name), &other)),
+    }
+}
+
+impl Serialize for f64 {
+    fn serialize(&self) -> JsonType {
+        JsonType::Num(Num::Float(*self))
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serializer.serialize_f64(*self)
+    }
+}
+
+impl Deserialize for f64 {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        num_to_float(data, "f64")
+    }
+}
+
+impl Serialize for f32 {
+    fn serialize(&self) -> JsonType {
+        JsonType::Num(Num::Float(*self as f64))
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serializer.serialize_f64(*self as f64)
+    }
+}
+
+impl Deserialize for f32 {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        let value = num_to_float(data, "f32")?;
+        let narrowed = value as f32;
+        if narrowed.is_finite() {
+            Ok(narrowed)
+        } else {
+            Err(out_of_range(&value, "f32"))
+        }
+    }
+}
+
+fn into_array(data: JsonType) -> Result<Vec<JsonType>, JsonError> {
+    match data {
+        JsonType::Array(array) => Ok(array),
+        other => Err(invalid_type("an array", &other)),
+    }
+}
+
+fn serialize_sequence<'a, T, I>(values: I) -> JsonType
+where
+    T: Serialize + 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    JsonType::Array(values.into_iter().map(Serialize::serialize).collect())
+}
+
+fn serialize_sequence_into<'a, T, I, S>(values: I, serializer: &mut S) -> Result<(), JsonError>
+where
+    T: Serialize + 'a,
+    I: IntoIterator<Item = &'a T>,
+    S: Serializer + ?Sized,
+{
+    let mut seq = serializer.serialize_seq()?;
+    for value in values {
+        seq.serialize_element(value)?;
+    }
+    seq.end()
+}
+
+fn deserialize_sequence<T, C>(data: JsonType) -> Result<C, JsonError>
+where
+    T: Deserialize,
+    C: FromIterator<T>,
+{
+    into_array(data)?.into_iter().map(T::deserialize).collect()
+}
+
+impl<T: Serialize> Serialize for [T] {
+    fn serialize(&self) -> JsonType {
+        serialize_sequence(self)
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serialize_sequence_into(self, serializer)
+    }
+}
+
+impl<T: Serialize> Serialize for Vec<T> {
+    fn serialize(&self) -> JsonType {
+        serialize_sequence(self)
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serialize_sequence_into(self, serializer)
+    }
+}
+
+impl<T: Deserialize> Deserialize for Vec<T> {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        deserialize_sequence(data)
+    }
+}
+
+impl<T: Serialize> Serialize for VecDeque<T> {
+    fn serialize(&self) -> JsonType {
+        serialize_sequence(self)
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serialize_sequence_into(self, serializer)
+    }
+}
+
+impl<T: Deserialize> Deserialize for VecDeque<T> {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        deserialize_sequence(data)
+    }
+}
+
+impl<T: Serialize, const N: usize> Serialize for [T; N] {
+    fn serialize(&self) -> JsonType {
+        serialize_sequence(self)
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serialize_sequence_into(self, serializer)
+    }
+}
+
+impl<T: Deserialize, const N: usize> Deserialize for [T; N] {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        let values: Vec<T> = deserialize_sequence(data)?;
+        let length = values.len();
+        values.try_into().map_err(|_| invalid_length(N, length))
+    }
+}
+
+impl<T: Serialize, H> Serialize for HashSet<T, H> {
+    fn serialize(&self) -> JsonType {
+        serialize_sequence(self)
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serialize_sequence_into(self, serializer)
+    }
+}
+
+impl<T, S> Deserialize for HashSet<T, S>
+where
+    T: Deserialize + Eq + Hash,
+    S: BuildHasher + Default,
+{
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        deserialize_sequence(data)
+    }
+}
+
+impl<T: Serialize> Serialize for BTreeSet<T> {
+    fn serialize(&self) -> JsonType {
+        serialize_sequence(self)
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serialize_sequence_into(self, serializer)
+    }
+}
+
+impl<T: Deserialize + Ord> Deserialize for BTreeSet<T> {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        deserialize_sequence(data)
+    }
+}
+
+macro_rules! impl_tuple {
+    ($length:literal => $($name:ident $index:tt),+) => {
+        impl<$($name: Serialize),+> Serialize for ($($name,)+) {
+            fn serialize(&self) -> JsonType {
+                JsonType::Array(vec![$(self.$index.serialize()),+])
+            }
+
+            fn serialize_into<S: Serializer + ?Sized>(
+                &self,
+                serializer: &mut S,
+            ) -> Result<(), JsonError> {
+                let mut seq = serializer.serialize_seq()?;
+                $(seq.serialize_element(&self.$index)?;)+
+                seq.end()
+            }
+        }
+
+        impl<$($name: Deserialize),+> Deserialize for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+                let array = into_array(data)?;
+                let length = array.len();
+                let [$($name),+] = <[JsonType; $length]>::try_from(array)
+                    .map_err(|_| invalid_length($length, length))?;
+                Ok(($($name::deserialize($name)?,)+))
+            }
+        }
+    };
+}
+
+impl_tuple!(1 => T0 0);
+impl_tuple!(2 => T0 0, T1 1);
+impl_tuple!(3 => T0 0, T1 1, T2 2);
+impl_tuple!(4 => T0 0, T1 1, T2 2, T3 3);
+impl_tuple!(5 => T0 0, T1 1, T2 2, T3 3, T4 4);
+impl_tuple!(6 => T0 0, T1 1, T2 2, T3 3, T4 4, T5 5);
+impl_tuple!(7 => T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6);
+impl_tuple!(8 => T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7);
+impl_tuple!(9 => T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8);
+impl_tuple!(10 => T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9);
+impl_tuple!(11 => T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10);
+impl_tuple!(12 => T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7, T8 8, T9 9, T10 10, T11 11);
+
+fn serialize_map<'a, K, V, I>(entries: I) -> JsonType
+where
+    K: AsRef<str> + 'a,
+    V: Serialize + 'a,
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+{
+    let data = entries
+        .into_iter()
+        .map(|(key, value)| (key.as_ref().to_string(), value.serialize()))
+        .collect();
+    JsonType::Object(Box::new(Object::new(data)))
+}
+
+fn serialize_map_into<'a, K, V, I, S>(entries: I, serializer: &mut S) -> Result<(), JsonError>
+where
+    K: AsRef<str> + 'a,
+    V: Serialize + 'a,
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+    S: Serializer + ?Sized,
+{
+    let mut map = serializer.serialize_map()?;
+    for (key, value) in entries {
+        map.serialize_entry(key.as_ref(), value)?;
+    }
+    map.end()
+}
+
+fn deserialize_map<K, V, C>(data: JsonType) -> Result<C, JsonError>
+where
+    K: From<String>,
+    V: Deserialize,
+    C: FromIterator<(K, V)>,
+{
+    match data {
+        JsonType::Object(object) => object
+            .into_iter()
+            .map(|(key, value)| Ok((K::from(key), V::deserialize(value)?)))
+            .collect(),
+        other => Err(invalid_type("an object", &other)),
+    }
+}
+
+impl<K: AsRef<str>, V: Serialize, H> Serialize for HashMap<K, V, H> {
+    fn serialize(&self) -> JsonType {
+        serialize_map(self)
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serialize_map_into(self, serializer)
+    }
+}
+
+impl<K, V, S> Deserialize for HashMap<K, V, S>
+where
+    K: From<String> + Eq + Hash,
+    V: Deserialize,
+    S: BuildHasher + Default,
+{
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        deserialize_map(data)
+    }
+}
+
+impl<K: AsRef<str>, V: Serialize> Serialize for BTreeMap<K, V> {
+    fn serialize(&self) -> JsonType {
+        serialize_map(self)
+    }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        serialize_map_into(self, serializer)
+    }
+}
+
+impl<K: From<String> + Ord, V: Deserialize> Deserialize for BTreeMap<K, V> {
+    fn deserialize(data: JsonType) -> Result<Self, JsonError> {
+        deserialize_map(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_string, to_string};
+
+    #[test]
+    fn it_should_map_option_to_null() {
+        let none: Option<JsonType> = None;
+        let some = Some(JsonType::Bool(true));
+
+        assert_eq!(JsonType::Null, none.serialize());
+        assert_eq!(JsonType::Bool(true), some.serialize());
+    }
+
+    #[test]
+    fn it_should_round_trip_primitives() {
+        assert_eq!(Ok(42u8), from_string("42"));
+        assert_eq!(Ok(-7i32), from_string("-7"));
+        assert_eq!(Ok(u64::MAX), from_string("18446744073709551615"));
+        assert_eq!(Ok(1.5f64), from_string("1.5"));
+        assert_eq!(Ok(3.0f32), from_string("3"));
+        assert_eq!(Ok(true), from_string("true"));
+        assert_eq!(Ok('é'), from_string(r#""é""#));
+        assert_eq!(Ok("hi".to_string()), from_string(r#""hi""#));
+
+        assert_eq!("18446744073709551615", to_string(u64::MAX).unwrap());
+        assert_eq!(
+            "340282366920938463463374607431768211455",
+            to_string(u128::MAX).unwrap()
+        );
+        assert_eq!("\"hi\"", to_string("hi").unwrap());
+        assert_eq!("\"c\"", to_string('c').unwrap());
+    }
+
+    #[test]
+    fn it_should_deserialize_big_integers_from_decimal() {
+        let options = crate::ParserOptions {
+            number_overflow: crate::NumberOverflow::Decimal,
+            ..crate::ParserOptions::default()
+        };
+        let value: u128 =
+            crate::from_string_with_options("340282366920938463463374607431768211455", options)
+                .unwrap();
+
+        assert_eq!(u128::MAX, value);
+    }
+
+    #[test]
+    fn it_should_report_out_of_range_integers() {
+        assert_eq!(
+            Err(JsonError::DeserializationError(
+                "300 is out of range for u8".to_string()
+            )),
+            from_string::<u8>("300")
+        );
+        assert_eq!(
+            Err(JsonError::DeserializationError(
+                "-1 is out of range for u32".to_string()
+            )),
+            from_string::<u32>("-1")
+        );
+        assert_eq!(
+            Err(JsonError::DeserializationError(
+                "expected an integer for i64, got 1.5".to_string()
+            )),
+            from_string::<i64>("1.5")
+        );
+        assert_eq!(
+            Err(JsonError::DeserializationError(
+                "expected an integer for u16, got a string".to_string()
+            )),
+            from_string::<u16>(r#""1""#)
+        );
+    }
+
+    #[test]
+    fn it_should_report_invalid_char() {
+        assert_eq!(
+            Err(JsonError::DeserializationError(
+                "expected a single character, got \"ab\"".to_string()
+            )),
+            from_string::<char>(r#""ab""#)
+        );
+    }
+
+    #[test]
+    fn it_should_round_trip_sequences() {
+        assert_eq!(Ok(vec![1u32, 2, 3]), from_string("[1, 2, 3]"));
+        assert_eq!(
+            Ok(VecDeque::from([true, false])),
+            from_string("[true, false]")
+        );
+        assert_eq!(Ok([1i8, 2]), from_string("[1, 2]"));
+        assert_eq!(
+            Ok((1u8, "a".to_string(), None::<bool>)),
+            from_string(r#"[1, "a", null]"#)
+        );
+        assert_eq!(Ok(BTreeSet::from([1, 2])), from_string("[2, 1, 2]"));
+        assert_eq!(Ok(HashSet::from([3u8])), from_string("[3]"));
+
+        assert_eq!("[1,2,3]", to_string(vec![1, 2, 3]).unwrap());
+        assert_eq!("[1,\"a\"]", to_string((1, "a")).unwrap());
+        assert_eq!("[[1],[]]", to_string([vec![1], vec![]]).unwrap());
+        assert_eq!("[1,2]", to_string(BTreeSet::from([2, 1])).unwrap());
+    }
+
+    #[test]
+    fn it_should_report_invalid_array_length() {
+        assert_eq!(
+            Err(JsonError::DeserializationError(
+                "expected an array of 2 elements, got 3".to_string()
+            )),
+            from_string::<[u8; 2]>("[1, 2, 3]")
+        );
+        assert_eq!(
+            Err(JsonError::DeserializationError(
+                "expected an array of 2 elements, got 1".to_string()
+            )),
+            from_string::<(u8, u8)>("[1]")
+        );
+    }
+
+    #[test]
+    fn it_should_round_trip_maps() {
+        let map: HashMap<String, f64> = from_string(r#"{"a": 1.5, "b": 2}"#).unwrap();
+        assert_eq!(Some(&1.5), map.get("a"));
+        assert_eq!(Some(&2.0), map.get("b"));
+
+        let map = BTreeMap::from([("b", 2), ("a", 1)]);
+        assert_eq!(r#"{"a":1,"b":2}"#, to_string(&map).unwrap());
+
+        assert_eq!(
+            Err(JsonError::DeserializationError(
+                "expected an object, got an array".to_string()
+            )),
+            from_string::<BTreeMap<String, u8>>("[]")
+        );
+    }
+}
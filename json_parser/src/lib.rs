@@ -20,17 +20,30 @@
 
 use crate::error::JsonError;
 use crate::parser::Parser;
-use crate::serializer::serialize_json;
+use crate::serializer::{SerializeMap, SerializeSeq, Serializer, serialize_num};
 use crate::token::tokenizer::Tokenizer;
 use crate::types::Num;
 use std::ops::Index;
 
+pub mod cst;
+pub mod de;
 pub mod error;
+mod impls;
+pub mod lines;
+pub mod options;
 pub mod parser;
+pub mod pointer;
+#[doc(hidden)]
+pub mod private;
+pub mod pull;
 pub mod serializer;
+pub mod stream;
 pub mod token;
 pub mod types;
 
+pub use json_parser_derive::{Deserialize, Serialize};
+pub use options::{DuplicateKeys, NumberOverflow, ParserOptions, RECURSION_LIMIT};
+pub use serializer::{PrettyFormatter, to_value, to_vec, to_writer};
 pub use types::Object;
 
 /// A parsed JSON enum that can be indexed by string keys or numeric indices
@@ -47,14 +60,140 @@ pub use types::Object;
 /// // Access values using string indexing
 /// assert_eq!(json["name"], JsonType::Str("Alice".to_string()));
 /// ```
+///
+/// Cloning and comparing values work whatever their depth. Dropping, `Debug`
+/// and `Serialize::serialize` recurse once per nesting level, which is fine
+/// within the default [`ParserOptions::max_depth`]. A value parsed without
+/// that limit may be too deep for them: drop it with
+/// [`JsonType::drop_iteratively`], while serializing it fails past
+/// [`RECURSION_LIMIT`].
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug)]
 pub enum JsonType {
     Str(String),
     Num(Num),
     Bool(bool),
     Object(Box<Object>),
     Array(Vec<JsonType>),
+    Null,
+}
+
+/// Moves the elements or member values of `value` to `pending`
+fn take_children(value: &mut JsonType, pending: &mut Vec<JsonType>) {
+    match value {
+        JsonType::Array(values) => pending.append(values),
+        JsonType::Object(object) => pending.extend(object.drain_values()),
+        _ => {}
+    }
+}
+
+/// Copies values with a stack of partially built containers rather than
+/// recursively
+impl Clone for JsonType {
+    fn clone(&self) -> Self {
+        let mut stack: Vec<CloneFrame> = Vec::new();
+        let mut next = self;
+        loop {
+            let mut value = match next {
+                JsonType::Array(values) => {
+                    stack.push(CloneFrame::Array(
+                        values.iter(),
+                        Vec::with_capacity(values.len()),
+                    ));
+                    None
+                }
+                JsonType::Object(object) => {
+                    let members = object.members();
+                    stack.push(CloneFrame::Object(
+                        members.iter(),
+                        Vec::with_capacity(members.len()),
+                        "",
+                    ));
+                    None
+                }
+                JsonType::Str(value) => Some(JsonType::Str(value.clone())),
+                JsonType::Num(value) => Some(JsonType::Num(value.clone())),
+                JsonType::Bool(value) => Some(JsonType::Bool(*value)),
+                JsonType::Null => Some(JsonType::Null),
+            };
+
+            // Hands the finished value to its parent until a parent has more
+            // children to copy
+            loop {
+                let Some(frame) = stack.last_mut() else {
+                    return value.expect("the root is finished once the stack is empty");
+                };
+                match frame {
+                    CloneFrame::Array(children, values) => {
+                        values.extend(value.take());
+                        if let Some(child) = children.next() {
+                            next = child;
+                            break;
+                        }
+                    }
+                    CloneFrame::Object(children, members, key) => {
+                        members.extend(value.take().map(|value| (key.to_string(), value)));
+                        if let Some((child_key, child)) = children.next() {
+                            *key = child_key.as_str();
+                            next = child;
+                            break;
+                        }
+                    }
+                }
+                value = stack.pop().map(|frame| match frame {
+                    CloneFrame::Array(_, values) => JsonType::Array(values),
+                    CloneFrame::Object(_, members, _) => {
+                        JsonType::Object(Box::new(Object::new(members)))
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// A container being copied by [`JsonType::clone`], with the children left to
+/// copy and the copies made so far
+enum CloneFrame<'a> {
+    Array(std::slice::Iter<'a, JsonType>, Vec<JsonType>),
+    Object(
+        std::slice::Iter<'a, (String, JsonType)>,
+        Vec<(String, JsonType)>,
+        &'a str,
+    ),
+}
+
+/// Compares values with a work list rather than recursively
+impl PartialEq for JsonType {
+    fn eq(&self, other: &Self) -> bool {
+        let mut pending = vec![(self, other)];
+        while let Some(pair) = pending.pop() {
+            match pair {
+                (JsonType::Array(left), JsonType::Array(right)) => {
+                    if left.len() != right.len() {
+                        return false;
+                    }
+                    pending.extend(left.iter().zip(right));
+                }
+                (JsonType::Object(left), JsonType::Object(right)) => {
+                    if left.len() != right.len() {
+                        return false;
+                    }
+                    for ((left_key, left), (right_key, right)) in left.iter().zip(right.iter()) {
+                        if left_key != right_key {
+                            return false;
+                        }
+                        pending.push((left, right));
+                    }
+                }
+                (JsonType::Str(left), JsonType::Str(right)) if left == right => {}
+                (JsonType::Num(left), JsonType::Num(right)) if left == right => {}
+                (JsonType::Bool(left), JsonType::Bool(right)) if left == right => {}
+                (JsonType::Null, JsonType::Null) => {}
+                _ => return false,
+            }
+        }
+        true
+    }
 }
 
 // This impl allow the following use case:
@@ -70,12 +209,149 @@ impl Serialize for JsonType {
     fn serialize(&self) -> JsonType {
         self.clone()
     }
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        match self {
+            JsonType::Str(value) => serializer.serialize_str(value),
+            JsonType::Num(value) => serialize_num(value, serializer),
+            JsonType::Bool(value) => serializer.serialize_bool(*value),
+            JsonType::Null => serializer.serialize_null(),
+            JsonType::Array(values) => {
+                let mut seq = serializer.serialize_seq()?;
+                for value in values {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+            JsonType::Object(object) => {
+                let mut map = serializer.serialize_map()?;
+                for (key, value) in object.iter() {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl JsonType {
+    /// Returns the value of `key` if this is an object which has it
+    pub fn get(&self, key: &str) -> Option<&JsonType> {
+        self.as_object()?.get(key)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JsonType> {
+        match self {
+            JsonType::Object(object) => object.get_mut(key),
+            _ => None,
+        }
+    }
+
+    /// Returns the element at `index` if this is an array long enough
+    pub fn get_index(&self, index: usize) -> Option<&JsonType> {
+        self.as_array()?.get(index)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonType::Str(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the number if it is an integer which fits in an `i64`
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonType::Num(Num::Integer(value)) => Some(*value),
+            JsonType::Num(Num::UInteger(value)) => i64::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns any number as an `f64`, rounding it if needed
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonType::Num(Num::Integer(value)) => Some(*value as f64),
+            JsonType::Num(Num::UInteger(value)) => Some(*value as f64),
+            JsonType::Num(Num::Float(value)) => Some(*value),
+            JsonType::Num(Num::Decimal(value)) => value.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonType::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonType>> {
+        match self {
+            JsonType::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Object> {
+        match self {
+            JsonType::Object(object) => Some(object),
+            _ => None,
+        }
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, JsonType::Str(_))
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, JsonType::Num(_))
+    }
+
+    pub fn is_i64(&self) -> bool {
+        self.as_i64().is_some()
+    }
+
+    pub fn is_f64(&self) -> bool {
+        matches!(self, JsonType::Num(Num::Float(_)))
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(self, JsonType::Bool(_))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, JsonType::Array(_))
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, JsonType::Object(_))
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonType::Null)
+    }
+
+    /// Moves the value out, leaving `Null` in its place
+    pub fn take(&mut self) -> JsonType {
+        std::mem::replace(self, JsonType::Null)
+    }
+
+    /// Drops the value from a work list, as the default drop glue recurses
+    /// once per nesting level and overflows the stack on very deep values
+    pub fn drop_iteratively(mut self) {
+        let mut pending = Vec::new();
+        take_children(&mut self, &mut pending);
+        while let Some(mut value) = pending.pop() {
+            take_children(&mut value, &mut pending);
+        }
+    }
 }
 
 impl Index<&str> for JsonType {
     type Output = JsonType;
 
-    fn index<'a, 'b>(&'a self, index: &'b str) -> &'a Self::Output {
+    fn index<'a>(&'a self, index: &str) -> &'a Self::Output {
         if let JsonType::Object(obj) = &self {
             return &obj[index];
         }
@@ -86,7 +362,7 @@ impl Index<&str> for JsonType {
 impl Index<usize> for JsonType {
     type Output = JsonType;
 
-    fn index<'a, 'b>(&'a self, index: usize) -> &'a Self::Output {
+    fn index(&self, index: usize) -> &Self::Output {
         if let JsonType::Array(obj) = &self {
             return &obj[index];
         }
@@ -135,7 +411,15 @@ impl Index<usize> for JsonType {
 /// assert_eq!(person.name, "Alice");
 /// ```
 pub fn from_string<T: Deserialize>(json_string: &str) -> Result<T, JsonError> {
-    let tokenizer = Tokenizer::new(json_string);
+    from_string_with_options(json_string, ParserOptions::default())
+}
+
+/// Same as [`from_string`] with custom [`ParserOptions`]
+pub fn from_string_with_options<T: Deserialize>(
+    json_string: &str,
+    options: ParserOptions,
+) -> Result<T, JsonError> {
+    let tokenizer = Tokenizer::with_options(json_string, options);
     let mut parser = Parser::new(tokenizer);
     let data = parser.parse_tokens()?;
     <T as Deserialize>::deserialize(data)
@@ -152,13 +436,34 @@ pub trait Deserialize {
         Self: Sized;
 }
 
+/// Serializes `input` into a compact JSON string
 pub fn to_string<T: Serialize>(input: T) -> Result<String, JsonError> {
-    let json_data = input.serialize();
-    serialize_json(&json_data)
+    let output = to_vec(&input)?;
+    String::from_utf8(output).map_err(|error| JsonError::SerializationError(error.to_string()))
+}
+
+/// Serializes `input` into an indented JSON string, see [`PrettyFormatter`]
+/// for other layouts
+pub fn to_string_pretty<T: Serialize>(input: T) -> Result<String, JsonError> {
+    PrettyFormatter::default().to_string(&input)
 }
 
+/// Trait for types that can be serialized into JSON
+///
+/// `serialize` builds a [`JsonType`]. `serialize_into` streams the value to a
+/// [`Serializer`] instead; it goes through `serialize` unless overridden, as
+/// the derive macro and the implementations of this crate do.
+///
+/// `serialize` cannot fail, so a derived implementation panics on a value it
+/// cannot represent, such as an internally tagged newtype variant holding a
+/// number. [`to_value`], [`to_string`] and the other serializing functions go
+/// through `serialize_into` and report it as an error instead.
 pub trait Serialize {
-    fn serialize(self: &Self) -> JsonType;
+    fn serialize(&self) -> JsonType;
+
+    fn serialize_into<S: Serializer + ?Sized>(&self, serializer: &mut S) -> Result<(), JsonError> {
+        self.serialize().serialize_into(serializer)
+    }
 }
 
 #[cfg(test)]
@@ -167,15 +472,140 @@ mod tests {
 
     #[test]
     fn it_should_serialize_jsontype() {
-        let v = Object {
-            data: vec![
-                ("key1".to_string(), JsonType::Str("hello".to_string())),
-                ("key2".to_string(), JsonType::Bool(true)),
-            ],
-        };
+        let v = Object::new(vec![
+            ("key1".to_string(), JsonType::Str("hello".to_string())),
+            ("key2".to_string(), JsonType::Bool(true)),
+        ]);
         let input = JsonType::Object(Box::new(v));
         let result: String = to_string(input).unwrap();
 
         assert_eq!("{\"key1\":\"hello\",\"key2\":true}", result);
     }
+
+    #[test]
+    fn it_should_deserialize_null_field_into_none() {
+        let json: JsonType = from_string(r#"{"name": null, "age": 30}"#).unwrap();
+
+        let name: Option<JsonType> = Deserialize::deserialize(json["name"].clone()).unwrap();
+        let age: Option<JsonType> = Deserialize::deserialize(json["age"].clone()).unwrap();
+
+        assert_eq!(None, name);
+        assert_eq!(Some(JsonType::Num(Num::Integer(30))), age);
+    }
+
+    #[test]
+    fn it_should_round_trip_escaped_strings() {
+        let json_str = r#"{"quote":"say \"hi\"","path":"C:\\tmp","emoji":"\ud83d\ude00"}"#;
+        let json: JsonType = from_string(json_str).unwrap();
+
+        assert_eq!(json["emoji"], JsonType::Str("😀".to_string()));
+        assert_eq!(
+            r#"{"quote":"say \"hi\"","path":"C:\\tmp","emoji":"😀"}"#,
+            to_string(json).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_round_trip_negative_zero() {
+        let json: JsonType = from_string("[-0, 0]").unwrap();
+
+        assert_eq!("[-0,0]", to_string(&json).unwrap());
+        assert_eq!(0, from_string::<i32>("-0").unwrap());
+        assert_eq!(0, crate::de::from_str::<u8>("-0").unwrap());
+    }
+
+    #[test]
+    fn it_should_index_the_last_value_of_a_repeated_key_by_default() {
+        let json: JsonType = from_string(r#"{"a":1,"a":2}"#).unwrap();
+        assert_eq!(JsonType::Num(Num::Integer(2)), json["a"]);
+
+        let options = ParserOptions {
+            duplicate_keys: DuplicateKeys::First,
+            ..ParserOptions::default()
+        };
+        let json: JsonType = from_string_with_options(r#"{"a":1,"a":2}"#, options).unwrap();
+        assert_eq!(JsonType::Num(Num::Integer(1)), json["a"]);
+    }
+
+    #[test]
+    fn it_should_move_values_out_of_variants() {
+        let json: JsonType = from_string(r#"{"tags": ["a"]}"#).unwrap();
+
+        let JsonType::Object(object) = json else {
+            panic!("expected an object");
+        };
+        let tags = match object.into_iter().next() {
+            Some((_, JsonType::Array(tags))) => tags,
+            other => panic!("expected an array, got {:?}", other),
+        };
+        assert_eq!(vec![JsonType::Str("a".to_string())], tags);
+    }
+
+    #[test]
+    fn it_should_access_values_without_panicking() {
+        let json: JsonType = from_string(
+            r#"{"name": "Alice", "age": 30, "score": 1.5, "admin": false, "tags": ["a"]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(Some("Alice"), json.get("name").and_then(JsonType::as_str));
+        assert_eq!(Some(30), json.get("age").and_then(JsonType::as_i64));
+        assert_eq!(Some(30.0), json.get("age").and_then(JsonType::as_f64));
+        assert_eq!(Some(1.5), json.get("score").and_then(JsonType::as_f64));
+        assert_eq!(None, json.get("score").and_then(JsonType::as_i64));
+        assert_eq!(Some(false), json.get("admin").and_then(JsonType::as_bool));
+        assert_eq!(
+            Some(1),
+            json.get("tags").and_then(JsonType::as_array).map(Vec::len)
+        );
+        assert_eq!(Some(5), json.as_object().map(Object::len));
+        assert_eq!(None, json.get("missing"));
+        assert_eq!(None, json.get_index(0));
+        assert_eq!(None, json["tags"].get_index(1));
+        assert_eq!(None, json["tags"].get("a"));
+        assert_eq!(None, JsonType::Num(Num::UInteger(u64::MAX)).as_i64());
+    }
+
+    #[test]
+    fn it_should_check_the_type_of_a_value() {
+        let json: JsonType = from_string(r#"[null, true, 1, 1.5, "a", [], {}]"#).unwrap();
+        let values = json.as_array().unwrap();
+
+        assert!(values[0].is_null());
+        assert!(values[1].is_bool());
+        assert!(values[2].is_number() && values[2].is_i64() && !values[2].is_f64());
+        assert!(values[3].is_number() && values[3].is_f64() && !values[3].is_i64());
+        assert!(values[4].is_string());
+        assert!(values[5].is_array() && !values[5].is_object());
+        assert!(values[6].is_object() && !values[6].is_array());
+    }
+
+    #[test]
+    fn it_should_take_and_mutate_values() {
+        let mut json: JsonType = from_string(r#"{"name": "Alice", "tags": ["a"]}"#).unwrap();
+
+        *json.get_mut("name").unwrap() = JsonType::Str("Bob".to_string());
+        let tags = json.get_mut("tags").unwrap().take();
+
+        assert_eq!(Some("Bob"), json["name"].as_str());
+        assert!(json["tags"].is_null());
+        assert!(tags.is_array());
+        assert_eq!(None, tags.clone().get_mut("a"));
+    }
+
+    #[test]
+    fn it_should_fail_to_serialize_json5_infinity() {
+        let options = ParserOptions {
+            json5: true,
+            ..ParserOptions::default()
+        };
+        let json: JsonType = from_string_with_options("[1, Infinity]", options).unwrap();
+
+        assert_eq!(
+            Err(JsonError::SerializationError(
+                "inf cannot be represented in JSON".to_string()
+            )),
+            to_string(json)
+        );
+    }
 }
@@ -0,0 +1,265 @@
+//! Newline-delimited JSON, one document per line
+//!
+//! # Examples
+//!
+//! ```
+//! use json_parser::lines::{JsonLinesReader, JsonLinesWriter};
+//! use json_parser::JsonType;
+//!
+//! let input = "{\"id\": 1}\n\n[true]\n".as_bytes();
+//! let values: Vec<JsonType> = JsonLinesReader::new(input)
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//!
+//! let mut writer = JsonLinesWriter::new(Vec::new());
+//! for value in &values {
+//!     writer.write(value).unwrap();
+//! }
+//! assert_eq!(b"{\"id\":1}\n[true]\n".to_vec(), writer.into_inner());
+//! ```
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::marker::PhantomData;
+
+use crate::error::JsonError;
+use crate::options::ParserOptions;
+use crate::serializer::to_writer;
+use crate::token::span::Position;
+use crate::{Deserialize, Serialize, from_string_with_options};
+
+/// Reads one `T` per line of a [`Read`]
+///
+/// Blank lines are skipped. Positions in errors point into the whole input,
+/// and errors without a position are prefixed with their line number.
+#[derive(Debug)]
+pub struct JsonLinesReader<R, T> {
+    reader: BufReader<R>,
+    options: ParserOptions,
+    skip_invalid_lines: bool,
+    /// Line and offset of the start of the next line
+    next: Position,
+    buffer: Vec<u8>,
+    failed: bool,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<R: Read, T: Deserialize> JsonLinesReader<R, T> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, ParserOptions::default())
+    }
+
+    pub fn with_options(reader: R, options: ParserOptions) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            options,
+            skip_invalid_lines: false,
+            next: Position::default(),
+            buffer: Vec::new(),
+            failed: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Skips the lines which do not hold a valid `T` instead of returning
+    /// their error, read errors are still returned
+    pub fn skip_invalid_lines(mut self, skip: bool) -> Self {
+        self.skip_invalid_lines = skip;
+        self
+    }
+
+    /// Returns the number of lines read so far
+    pub fn line(&self) -> usize {
+        self.next.line - 1
+    }
+
+    /// Parses the line held in `buffer` which starts at `start`
+    fn parse_line(&self, start: Position) -> Result<T, JsonError> {
+        let line = std::str::from_utf8(&self.buffer).map_err(|error| {
+            let valid = std::str::from_utf8(&self.buffer[..error.valid_up_to()]).unwrap_or("");
+            JsonError::InvalidUtf8(Position::new(valid.len(), 1, valid.chars().count() + 1))
+        });
+        line.and_then(|line| from_string_with_options(line, self.options.clone()))
+            .map_err(|error| locate(error, start))
+    }
+}
+
+impl<R: Read, T: Deserialize> Iterator for JsonLinesReader<R, T> {
+    type Item = Result<T, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            self.buffer.clear();
+            let read = match self.reader.read_until(b'\n', &mut self.buffer) {
+                Ok(0) => return None,
+                Ok(read) => read,
+                Err(error) => {
+                    self.failed = true;
+                    return Some(Err(error.into()));
+                }
+            };
+            let start = self.next;
+            self.next = Position::new(start.offset + read, start.line + 1, 1);
+            if self.buffer.ends_with(b"\n") {
+                self.buffer.pop();
+            }
+            if self.buffer.ends_with(b"\r") {
+                self.buffer.pop();
+            }
+            if self.buffer.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            match self.parse_line(start) {
+                Err(_) if self.skip_invalid_lines => continue,
+                result => return Some(result),
+            }
+        }
+        None
+    }
+}
+
+/// Moves the position of an error raised on a single line to where the line
+/// starts in the input
+fn locate(mut error: JsonError, start: Position) -> JsonError {
+    match error.position_mut() {
+        Some(position) => {
+            position.offset += start.offset;
+            position.line += start.line - 1;
+            error
+        }
+        None => match error {
+            JsonError::DeserializationError(message) => {
+                JsonError::DeserializationError(format!("line {}: {}", start.line, message))
+            }
+            error => error,
+        },
+    }
+}
+
+/// Writes values to a [`Write`], one compact document per line
+#[derive(Debug)]
+pub struct JsonLinesWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Writes `value` followed by a newline
+    pub fn write<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        to_writer(&mut self.writer, value)?;
+        Ok(self.writer.write_all(b"\n")?)
+    }
+
+    pub fn flush(&mut self) -> Result<(), JsonError> {
+        Ok(self.writer.flush()?)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JsonType;
+    use crate::token::token::Token;
+    use std::io;
+
+    fn read<T: Deserialize>(input: &str) -> Vec<Result<T, JsonError>> {
+        JsonLinesReader::new(input.as_bytes()).collect()
+    }
+
+    #[test]
+    fn it_should_read_one_value_per_line() {
+        let values: Vec<u32> = read("1\n2\r\n\n  \n3")
+            .into_iter()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(vec![1, 2, 3], values);
+    }
+
+    #[test]
+    fn it_should_locate_errors_in_the_whole_input() {
+        let results = read::<JsonType>("[1]\n\n{\"a\" 1}\n[2]\n");
+
+        assert_eq!(3, results.len());
+        assert_eq!(
+            Err(JsonError::CollonError(
+                Token::Int(1),
+                Position::new(10, 3, 6)
+            )),
+            results[1]
+        );
+        assert!(results[2].is_ok());
+    }
+
+    #[test]
+    fn it_should_prefix_deserialization_errors_with_the_line() {
+        let results = read::<bool>("true\n\"yes\"\n");
+
+        assert_eq!(
+            Err(JsonError::DeserializationError(
+                "line 2: expected a boolean, got a string".to_string()
+            )),
+            results[1]
+        );
+    }
+
+    #[test]
+    fn it_should_skip_invalid_lines() {
+        let input = &b"1\nx\n\xff\n2\n"[..];
+        let mut reader = JsonLinesReader::<_, i32>::new(input).skip_invalid_lines(true);
+
+        assert_eq!(Some(Ok(1)), reader.next());
+        assert_eq!(Some(Ok(2)), reader.next());
+        assert_eq!(4, reader.line());
+        assert_eq!(None, reader.next());
+    }
+
+    #[test]
+    fn it_should_report_invalid_utf8_on_its_line() {
+        let results: Vec<Result<JsonType, _>> =
+            JsonLinesReader::new(&b"1\n\"a\xff\"\n"[..]).collect();
+
+        assert_eq!(
+            Err(JsonError::InvalidUtf8(Position::new(4, 2, 3))),
+            results[1]
+        );
+    }
+
+    #[test]
+    fn it_should_stop_after_a_read_error() {
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk unplugged"))
+            }
+        }
+
+        let mut reader = JsonLinesReader::<_, JsonType>::new(FailingReader);
+
+        assert_eq!(
+            Some(Err(JsonError::IoError("disk unplugged".to_string()))),
+            reader.next()
+        );
+        assert_eq!(None, reader.next());
+    }
+
+    #[test]
+    fn it_should_write_one_value_per_line() {
+        let mut writer = JsonLinesWriter::new(Vec::new());
+        writer.write(&vec![1, 2]).unwrap();
+        writer.write("a\nb").unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(
+            "[1,2]\n\"a\\nb\"\n",
+            String::from_utf8(writer.into_inner()).unwrap()
+        );
+    }
+}
@@ -1,33 +1,11 @@
-use json_parser::types::Object;
-use json_parser::{Deserialize, JsonType, Serialize, error::JsonError};
+use json_parser::{Deserialize, JsonType, Serialize};
 use json_parser::{from_string, to_string};
 
+#[derive(Serialize, Deserialize)]
 struct Person {
     pub name: String,
 }
 
-impl Deserialize for Person {
-    fn deserialize(data: JsonType) -> Result<Person, JsonError> {
-        if let JsonType::Str(name) = &data["name"] {
-            return Ok(Self {
-                name: name.to_string(),
-            });
-        }
-        Err(JsonError::DeserializationError(
-            "Cannot find field string name in Json".to_string(),
-        ))
-    }
-}
-
-impl Serialize for Person {
-    fn serialize(&self) -> JsonType {
-        // Does user needs a helper function to build an object ?
-        let v = vec![("name".to_string(), JsonType::Str(self.name.clone()))];
-        let object = Object::new(v);
-        JsonType::Object(Box::new(object))
-    }
-}
-
 fn main() {
     // Example with a struct that implements Deserialize and Serialize
     let json = r#"
@@ -38,7 +16,7 @@ fn main() {
 
     println!("Hello: {}", j.name);
 
-    let serialized: String = to_string(j).unwrap();
+    let serialized: String = to_string(&j).unwrap();
     println!("Peson serialized: {}", serialized);
 
     // Example with generic data
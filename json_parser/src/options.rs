@@ -0,0 +1,115 @@
+/// Number of arrays and objects nested in each other that the serializers,
+/// the visitor based [`de::from_str`](crate::de::from_str) and
+/// [`cst::Document`](crate::cst::Document) handle
+///
+/// They recurse once per nesting level, so deeper values are an error rather
+/// than a stack overflow, whatever [`ParserOptions::max_depth`] allows: a
+/// `JsonError::SerializationError` when serializing and a
+/// `JsonError::DepthLimitExceeded` when reading. It is also the default
+/// `max_depth`.
+pub const RECURSION_LIMIT: usize = 128;
+
+/// Settings controlling how the [`Tokenizer`](crate::token::tokenizer::Tokenizer)
+/// and the [`Parser`](crate::parser::Parser) read a document
+///
+/// The `max_*` limits bound the resources used on untrusted input, `None`
+/// meaning no limit. Only the nesting depth is limited by default, to
+/// [`RECURSION_LIMIT`] levels. The parser itself handles any depth, but
+/// dropping a [`JsonType`](crate::JsonType), printing it with `Debug` and
+/// serializing it recurse once per level: within the default, whatever was
+/// parsed can be used with them. Deeper documents need `max_depth: None` or a
+/// higher limit, see [`JsonType::drop_iteratively`](crate::JsonType::drop_iteratively).
+///
+/// `json5` relaxes the grammar to accept [JSON5](https://json5.org) documents:
+/// comments, trailing commas, single quoted strings, unquoted keys,
+/// hexadecimal numbers, `Infinity`, `NaN` and a leading `+`.
+///
+/// # Examples
+///
+/// ```
+/// use json_parser::{from_string_with_options, JsonType, NumberOverflow, ParserOptions};
+///
+/// let options = ParserOptions {
+///     number_overflow: NumberOverflow::Error,
+///     ..ParserOptions::default()
+/// };
+/// let result: Result<JsonType, _> = from_string_with_options("1e400", options);
+/// assert!(result.is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParserOptions {
+    pub number_overflow: NumberOverflow,
+    pub duplicate_keys: DuplicateKeys,
+    /// Maximum number of arrays and objects nested in each other
+    ///
+    /// The parser reads any depth when this is `None`. The visitor based
+    /// [`de::from_str`](crate::de::from_str) and
+    /// [`cst::Document`](crate::cst::Document) recurse once per level, so they
+    /// stop at [`RECURSION_LIMIT`] levels even when this is `None` or higher.
+    pub max_depth: Option<usize>,
+    /// Maximum length in bytes of a string or a key, once unescaped
+    pub max_string_length: Option<usize>,
+    /// Maximum number of members in an object
+    pub max_object_members: Option<usize>,
+    /// Maximum size in bytes of the input
+    pub max_input_size: Option<usize>,
+    /// Accept JSON5 on top of JSON
+    ///
+    /// `Infinity` and `NaN` are read as non-finite floats, which JSON cannot
+    /// represent: writing them back with [`to_string`](crate::to_string) or
+    /// [`to_writer`](crate::to_writer) fails with a serialization error.
+    pub json5: bool,
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            number_overflow: NumberOverflow::default(),
+            duplicate_keys: DuplicateKeys::default(),
+            max_depth: Some(RECURSION_LIMIT),
+            max_string_length: None,
+            max_object_members: None,
+            max_input_size: None,
+            json5: false,
+        }
+    }
+}
+
+impl ParserOptions {
+    /// The depth at which the readers recursing once per level stop:
+    /// `max_depth`, but never more than [`RECURSION_LIMIT`]
+    pub(crate) fn recursion_limit(&self) -> usize {
+        self.max_depth
+            .map_or(RECURSION_LIMIT, |max| max.min(RECURSION_LIMIT))
+    }
+}
+
+/// What to do with a number that fits neither `i64`, `u64` nor a finite `f64`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NumberOverflow {
+    /// Return a `JsonError::NumberOverflow`
+    Error,
+    /// Round to the closest `f64`, numbers too large even for a float are an error
+    #[default]
+    Float,
+    /// Keep the exact digits in a `Num::Decimal`
+    Decimal,
+}
+
+/// Which value to keep when an object repeats a key
+///
+/// The key stays where it first appears in the object whatever the policy.
+///
+/// The default keeps the last value, so `json["a"]` is `2` for
+/// `{"a": 1, "a": 2}`. Objects used to keep every member and indexing returned
+/// the first one: use [`DuplicateKeys::First`] to get `1` as before.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DuplicateKeys {
+    /// Keep the first value and ignore the following ones
+    First,
+    /// Keep the last value, as most JSON parsers do
+    #[default]
+    Last,
+    /// Return a `JsonError::DuplicateKey` at the repeated key
+    Error,
+}
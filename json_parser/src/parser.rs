@@ -1,125 +1,214 @@
 use crate::error::JsonError;
-use crate::token::tokenizer::{Token, Tokenizer};
+use crate::options::DuplicateKeys;
+use crate::token::tokenizer::{Position, SpannedToken, StrSource, Token, Tokenizer};
 use crate::types::Num;
 use crate::{JsonType, Object};
 
-// TODO: handle error in malformatted string (should I check the whole string ahead ?)
+mod recover;
 
+pub use recover::Diagnostic;
+
+#[derive(Debug)]
 pub struct Parser<'a> {
-    tokenizer: Tokenizer<'a>,
+    tokenizer: Tokenizer<StrSource<'a>>,
+}
+
+/// An array or an object being parsed
+enum Frame {
+    Array(Vec<JsonType>),
+    /// `key` is the key of the member whose value is being parsed
+    Object {
+        object: Object,
+        key: String,
+        key_start: Position,
+        members: usize,
+    },
+}
+
+/// Converts a token holding a scalar value
+fn scalar(token: Token, start: Position) -> Result<JsonType, JsonError> {
+    match token {
+        Token::Str(value) => Ok(JsonType::Str(value)),
+        Token::Int(value) => Ok(JsonType::Num(Num::Integer(value))),
+        Token::UInt(value) => Ok(JsonType::Num(Num::UInteger(value))),
+        Token::Float(value) => Ok(JsonType::Num(Num::Float(value))),
+        Token::Decimal(value) => Ok(JsonType::Num(Num::Decimal(value))),
+        Token::Bool(value) => Ok(JsonType::Bool(value)),
+        Token::Null => Ok(JsonType::Null),
+        token => Err(JsonError::ValueError(token, start)),
+    }
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(tokenizer: Tokenizer<'a>) -> Parser<'a> {
+    pub fn new(tokenizer: Tokenizer<StrSource<'a>>) -> Parser<'a> {
         Self { tokenizer }
     }
 
+    /// Parses a single JSON value, any trailing token is an error
     pub fn parse_tokens(&mut self) -> Result<JsonType, JsonError> {
-        if let Some(token) = self.tokenizer.next() {
-            match token {
-                Token::OpenCurlybracket => {
-                    let data = self.parse_object()?;
-                    return Ok(JsonType::Object(Box::new(data)));
-                }
-                Token::OpenBracket => {
-                    let data = self.parse_array()?;
-                    return Ok(JsonType::Array(data));
-                }
-                _ => return Err(JsonError::UnexpectedToken(token)),
-            }
+        let Some(token) = self.next_token()? else {
+            return Err(JsonError::EmptyInput(self.position()));
         };
-        Err(JsonError::EmptyInput)
+        let data = self.get_value(token)?;
+        if let Some(SpannedToken { token, span }) = self.next_token()? {
+            return Err(JsonError::TrailingToken(token, span.start));
+        }
+        Ok(data)
     }
 
-    fn parse_object(&mut self) -> Result<Object, JsonError> {
-        let mut data: Vec<(String, JsonType)> = Vec::new();
+    /// Parses the next JSON value and leaves the tokens after it unread
+    ///
+    /// Returns `None` once only whitespace is left in the input.
+    pub fn parse_next(&mut self) -> Result<Option<JsonType>, JsonError> {
+        let Some(token) = self.next_token()? else {
+            return Ok(None);
+        };
+        self.get_value(token).map(Some)
+    }
 
-        loop {
-            let key_value = self.get_key_value_pair()?;
-            data.push(key_value);
-            if self.expect_coma_or_end_object()? {
-                break;
-            }
-        }
-        Ok(Object { data })
+    /// Pulls the next token, propagating tokenizer errors
+    fn next_token(&mut self) -> Result<Option<SpannedToken>, JsonError> {
+        self.tokenizer.next().transpose()
     }
 
-    fn get_key_value_pair(&mut self) -> Result<(String, JsonType), JsonError> {
-        let next_token = self.tokenizer.next();
-        let key = match next_token {
-            Some(key) => self.get_key(key)?,
-            None => return Err(JsonError::UnexpectedEndOfJson),
-        };
+    /// Position of the tokenizer, used to report errors at the end of the input
+    pub fn position(&self) -> Position {
+        self.tokenizer.position()
+    }
 
-        self.expect_colon()?;
+    /// Expects one more token, the input must not end here
+    fn expect_token(&mut self) -> Result<SpannedToken, JsonError> {
+        match self.next_token()? {
+            Some(token) => Ok(token),
+            None => Err(JsonError::UnexpectedEndOfJson(self.position())),
+        }
+    }
 
-        let next_token = self.tokenizer.next();
-        let value = match next_token {
-            Some(value) => self.get_value(value)?,
-            None => return Err(JsonError::UnexpectedEndOfJson),
-        };
+    /// Parses the value starting with `token`
+    ///
+    /// Arrays and objects are kept on an explicit stack rather than parsed by
+    /// recursive calls, so the depth of a document is not bound by the size
+    /// of the thread stack.
+    fn get_value(&mut self, mut token: SpannedToken) -> Result<JsonType, JsonError> {
+        let mut stack: Vec<Frame> = Vec::new();
+        loop {
+            let SpannedToken { token: first, span } = token;
+            let mut value = match first {
+                Token::OpenCurlybracket => {
+                    self.enter(&stack, span.start)?;
+                    let next = self.expect_token()?;
+                    if next.token == Token::CloseCurlybracket {
+                        JsonType::Object(Box::default())
+                    } else {
+                        let (key, key_start) = self.get_key(next, 1)?;
+                        stack.push(Frame::Object {
+                            object: Object::default(),
+                            key,
+                            key_start,
+                            members: 1,
+                        });
+                        token = self.expect_token()?;
+                        continue;
+                    }
+                }
+                Token::OpenBracket => {
+                    self.enter(&stack, span.start)?;
+                    let next = self.expect_token()?;
+                    if next.token == Token::CloseBracket {
+                        JsonType::Array(Vec::new())
+                    } else {
+                        stack.push(Frame::Array(Vec::new()));
+                        token = next;
+                        continue;
+                    }
+                }
+                first => scalar(first, span.start)?,
+            };
 
-        Ok((key, value))
+            // Adds the value to its container, closing the containers which end
+            // after it, until one expects another value
+            loop {
+                match stack.last_mut() {
+                    None => return Ok(value),
+                    Some(Frame::Array(values)) => {
+                        values.push(value);
+                        if !self.expect_coma_or_end_array()? {
+                            token = self.expect_token()?;
+                            break;
+                        }
+                    }
+                    Some(Frame::Object {
+                        object,
+                        key,
+                        key_start,
+                        members,
+                    }) => {
+                        let name = std::mem::take(key);
+                        match self.tokenizer.options().duplicate_keys {
+                            DuplicateKeys::First if object.contains_key(&name) => {}
+                            DuplicateKeys::Error if object.contains_key(&name) => {
+                                return Err(JsonError::DuplicateKey(name, *key_start));
+                            }
+                            _ => {
+                                object.insert(name, value);
+                            }
+                        }
+                        if !self.expect_coma_or_end_object()? {
+                            *members += 1;
+                            let next = self.expect_token()?;
+                            (*key, *key_start) = self.get_key(next, *members)?;
+                            token = self.expect_token()?;
+                            break;
+                        }
+                    }
+                }
+                value = match stack.pop() {
+                    Some(Frame::Array(values)) => JsonType::Array(values),
+                    Some(Frame::Object { object, .. }) => JsonType::Object(Box::new(object)),
+                    None => unreachable!("a container was just closed"),
+                };
+            }
+        }
     }
 
-    fn get_key(&mut self, token: Token) -> Result<String, JsonError> {
-        if let Token::Str(key) = token {
-            return Ok(key);
+    /// Reads the key of the `members`-th member of an object and the colon
+    /// after it, returning the key and where it starts
+    fn get_key(
+        &mut self,
+        token: SpannedToken,
+        members: usize,
+    ) -> Result<(String, Position), JsonError> {
+        let SpannedToken { token, span } = token;
+        if let Some(max) = self.tokenizer.options().max_object_members
+            && members > max
+        {
+            return Err(JsonError::TooManyMembers(max, span.start));
         }
-        Err(JsonError::KeyError(token))
+        let (Token::Str(key) | Token::Identifier(key)) = token else {
+            return Err(JsonError::KeyError(token, span.start));
+        };
+        self.expect_colon()?;
+        Ok((key, span.start))
     }
 
     fn expect_colon(&mut self) -> Result<(), JsonError> {
-        let token = self.tokenizer.next();
-        if let Some(token) = token {
+        let token = self.next_token()?;
+        if let Some(SpannedToken { token, span }) = token {
             if token == Token::Colon {
                 return Ok(());
             }
-            return Err(JsonError::CollonError(token));
+            return Err(JsonError::CollonError(token, span.start));
         }
-        Err(JsonError::UnexpectedEndOfJson)
+        Err(JsonError::UnexpectedEndOfJson(self.position()))
     }
 
-    fn get_value(&mut self, token: Token) -> Result<JsonType, JsonError> {
-        match token {
-            Token::Str(value) => return Ok(JsonType::Str(value)),
-            Token::Int(value) => {
-                let num = Num::Integer(value);
-                return Ok(JsonType::Num(num));
-            }
-            Token::Float(value) => {
-                let num = Num::Float(value);
-                return Ok(JsonType::Num(num));
-            }
-            Token::Bool(value) => return Ok(JsonType::Bool(value)),
-            Token::OpenCurlybracket => {
-                let nested_object = self.parse_object()?;
-                return Ok(JsonType::Object(Box::new(nested_object)));
-            }
-            Token::OpenBracket => {
-                let array = self.parse_array()?;
-                return Ok(JsonType::Array(array));
-            }
-            _ => {}
+    /// Checks that one more container fits in the depth limit, its opening
+    /// bracket being at `start`
+    fn enter(&self, stack: &[Frame], start: Position) -> Result<(), JsonError> {
+        match self.tokenizer.options().max_depth {
+            Some(max) if stack.len() >= max => Err(JsonError::DepthLimitExceeded(max, start)),
+            _ => Ok(()),
         }
-        Err(JsonError::ValueError(token))
-    }
-
-    fn parse_array(&mut self) -> Result<Vec<JsonType>, JsonError> {
-        let mut arr: Vec<JsonType> = Vec::new();
-        loop {
-            let token = self.tokenizer.next();
-            if let Some(token) = token {
-                let value = self.get_value(token)?;
-                arr.push(value);
-                if self.expect_coma_or_end_array()? {
-                    break;
-                }
-            } else {
-                return Err(JsonError::UnexpectedEndOfJson);
-            }
-        }
-        Ok(arr)
     }
 
     /// Expects either a comma or closing curly bracket after a key-value pair.
@@ -128,20 +217,26 @@ impl<'a> Parser<'a> {
     /// - `Ok(true)` if the object ends (closing curly bracket encountered)
     /// - `Ok(false)` if there are more key-value pairs (comma encountered)
     /// - `Err` if an invalid token is encountered or if the JSON ends unexpectedly
+    ///
+    /// JSON5 allows a comma right before the closing curly bracket.
     fn expect_coma_or_end_object(&mut self) -> Result<bool, JsonError> {
-        if let Some(next_token) = self.tokenizer.next() {
-            match next_token {
+        if let Some(SpannedToken { token, span }) = self.next_token()? {
+            match token {
                 Token::Comma => {
                     if self.tokenizer.is_next_token_closing_curly_bracket() {
-                        return Err(JsonError::InvalidComaEndObjectError);
+                        if self.tokenizer.options().json5 {
+                            self.next_token()?;
+                            return Ok(true);
+                        }
+                        return Err(JsonError::InvalidComaEndObjectError(span.start));
                     }
                     return Ok(false);
                 }
                 Token::CloseCurlybracket => return Ok(true),
-                _ => return Err(JsonError::EndObjectError(next_token)),
+                _ => return Err(JsonError::EndObjectError(token, span.start)),
             }
         }
-        Err(JsonError::UnexpectedEndOfJson)
+        Err(JsonError::UnexpectedEndOfJson(self.position()))
     }
 
     /// Expects either a comma or closing bracket after a key-value pair.
@@ -150,26 +245,35 @@ impl<'a> Parser<'a> {
     /// - `Ok(true)` if the object ends (closing bracket encountered)
     /// - `Ok(false)` if there are more key-value pairs (comma encountered)
     /// - `Err` if an invalid token is encountered or if the JSON ends unexpectedly
+    ///
+    /// JSON5 allows a comma right before the closing bracket.
     fn expect_coma_or_end_array(&mut self) -> Result<bool, JsonError> {
-        if let Some(next_token) = self.tokenizer.next() {
-            match next_token {
+        if let Some(SpannedToken { token, span }) = self.next_token()? {
+            match token {
                 Token::Comma => {
                     if self.tokenizer.is_next_token_closing_curly_bracket() {
-                        return Err(JsonError::InvalidComaEndObjectError);
+                        return Err(JsonError::InvalidComaEndObjectError(span.start));
+                    }
+                    if self.tokenizer.options().json5
+                        && self.tokenizer.is_next_token_closing_bracket()
+                    {
+                        self.next_token()?;
+                        return Ok(true);
                     }
                     return Ok(false);
                 }
                 Token::CloseBracket => return Ok(true),
-                _ => return Err(JsonError::EndObjectError(next_token)),
+                _ => return Err(JsonError::EndObjectError(token, span.start)),
             }
         }
-        Err(JsonError::UnexpectedEndOfJson)
+        Err(JsonError::UnexpectedEndOfJson(self.position()))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::options::ParserOptions;
     use crate::token::tokenizer::Tokenizer;
 
     #[test]
@@ -237,7 +341,66 @@ mod tests {
     }
 
     #[test]
-    fn it_should_return_an_error_when_not_starting_from_curbly_bracket() {
+    fn it_should_parse_null_values() {
+        let json = r#"{"key1": null, "key2": [null, 1]}"#;
+        let tokenizer = Tokenizer::new(json);
+        let mut parser = Parser::new(tokenizer);
+        let json = parser.parse_tokens().unwrap();
+        assert_eq!(json["key1"], JsonType::Null);
+        assert_eq!(json["key2"][0], JsonType::Null);
+        assert_eq!(json["key2"][1], JsonType::Num(Num::Integer(1)));
+    }
+
+    #[test]
+    fn it_should_return_tokenizer_errors_instead_of_panicking() {
+        let json = r#"{"key1": "value1", "key2": @}"#;
+        let tokenizer = Tokenizer::new(json);
+        let mut parser = Parser::new(tokenizer);
+        assert_eq!(
+            parser.parse_tokens(),
+            Err(JsonError::UnexpectedCharacter(
+                '@',
+                Position::new(27, 1, 28)
+            ))
+        );
+    }
+
+    #[test]
+    fn it_should_parse_any_value_at_the_top_level() {
+        let cases = [
+            ("\"hello\"", JsonType::Str("hello".to_string())),
+            ("42", JsonType::Num(Num::Integer(42))),
+            ("-1.5", JsonType::Num(Num::Float(-1.5))),
+            ("true", JsonType::Bool(true)),
+            (" null ", JsonType::Null),
+        ];
+        for (json, expected) in cases {
+            let tokenizer = Tokenizer::new(json);
+            let mut parser = Parser::new(tokenizer);
+            assert_eq!(parser.parse_tokens(), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn it_should_parse_empty_containers() {
+        let json = r#"{"key1": {}, "key2": [], "key3": [{}, []]}"#;
+        let tokenizer = Tokenizer::new(json);
+        let mut parser = Parser::new(tokenizer);
+        let json = parser.parse_tokens().unwrap();
+        assert_eq!(
+            json["key1"],
+            JsonType::Object(Box::new(Object::new(vec![])))
+        );
+        assert_eq!(json["key2"], JsonType::Array(vec![]));
+        assert_eq!(
+            json["key3"][0],
+            JsonType::Object(Box::new(Object::new(vec![])))
+        );
+        assert_eq!(json["key3"][1], JsonType::Array(vec![]));
+    }
+
+    #[test]
+    fn it_should_return_an_error_on_trailing_tokens() {
         let json = "\"a\"{";
         let tokenizer = Tokenizer::new(json);
         let mut parser = Parser::new(tokenizer);
@@ -245,10 +408,205 @@ mod tests {
         if let Err(error) = json {
             assert_eq!(
                 error,
-                JsonError::UnexpectedToken(Token::Str("a".to_string()))
+                JsonError::TrailingToken(Token::OpenCurlybracket, Position::new(3, 1, 4))
             );
         } else {
             panic!("Expect error")
         }
     }
+
+    #[test]
+    fn it_should_return_an_error_on_empty_input() {
+        let tokenizer = Tokenizer::new("  ");
+        let mut parser = Parser::new(tokenizer);
+        assert_eq!(
+            parser.parse_tokens(),
+            Err(JsonError::EmptyInput(Position::new(2, 1, 3)))
+        );
+    }
+
+    #[test]
+    fn it_should_report_the_position_of_a_missing_colon() {
+        let json = "{\n    \"key1\": 1,\n    \"key2\" 2\n}";
+        let tokenizer = Tokenizer::new(json);
+        let mut parser = Parser::new(tokenizer);
+        assert_eq!(
+            parser.parse_tokens(),
+            Err(JsonError::CollonError(
+                Token::Int(2),
+                Position::new(28, 3, 12)
+            ))
+        );
+    }
+
+    #[test]
+    fn it_should_report_the_end_of_input_position() {
+        let json = "[1, 2";
+        let tokenizer = Tokenizer::new(json);
+        let mut parser = Parser::new(tokenizer);
+        assert_eq!(
+            parser.parse_tokens(),
+            Err(JsonError::UnexpectedEndOfJson(Position::new(5, 1, 6)))
+        );
+    }
+
+    #[test]
+    fn it_should_apply_the_duplicate_keys_policy() {
+        let json = r#"{"a": 1, "b": 2, "a": 3}"#;
+        let parse = |duplicate_keys| {
+            let options = ParserOptions {
+                duplicate_keys,
+                ..ParserOptions::default()
+            };
+            Parser::new(Tokenizer::with_options(json, options))
+                .parse_tokens()
+                .unwrap()
+        };
+
+        let first = parse(DuplicateKeys::First);
+        let last = parse(DuplicateKeys::Last);
+
+        assert_eq!(JsonType::Num(Num::Integer(1)), first["a"]);
+        assert_eq!(JsonType::Num(Num::Integer(3)), last["a"]);
+        assert_eq!(
+            vec!["a", "b"],
+            last.as_object().unwrap().keys().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn it_should_reject_duplicate_keys() {
+        let json = "{\n  \"a\": {\"b\": 1, \"b\": 2}\n}";
+        let options = ParserOptions {
+            duplicate_keys: DuplicateKeys::Error,
+            ..ParserOptions::default()
+        };
+        let mut parser = Parser::new(Tokenizer::with_options(json, options.clone()));
+
+        assert_eq!(
+            Err(JsonError::DuplicateKey(
+                "b".to_string(),
+                Position::new(18, 2, 17)
+            )),
+            parser.parse_tokens()
+        );
+
+        let mut parser = Parser::new(Tokenizer::with_options(r#"{"a": {"a": 1}}"#, options));
+        assert!(parser.parse_tokens().is_ok());
+    }
+
+    #[test]
+    fn it_should_limit_the_nesting_depth() {
+        let parse = |json: &str, max_depth| {
+            let options = ParserOptions {
+                max_depth,
+                ..ParserOptions::default()
+            };
+            Parser::new(Tokenizer::with_options(json, options)).parse_tokens()
+        };
+
+        assert!(parse("[[{\"a\": []}]]", Some(4)).is_ok());
+        assert_eq!(
+            Err(JsonError::DepthLimitExceeded(3, Position::new(8, 1, 9))),
+            parse("[[{\"a\": []}]]", Some(3))
+        );
+        assert!(parse(&"[".repeat(64), None).is_err());
+
+        let deep = "[".repeat(100_000);
+        assert_eq!(
+            Err(JsonError::DepthLimitExceeded(
+                128,
+                Position::new(128, 1, 129)
+            )),
+            parse(&deep, ParserOptions::default().max_depth)
+        );
+    }
+
+    #[test]
+    fn it_should_limit_the_number_of_members() {
+        let options = ParserOptions {
+            max_object_members: Some(2),
+            ..ParserOptions::default()
+        };
+        let parse =
+            |json| Parser::new(Tokenizer::with_options(json, options.clone())).parse_tokens();
+
+        assert!(parse(r#"[{"a": 1, "b": 2}, {"c": {"d": 1, "e": 2}}]"#).is_ok());
+        assert_eq!(
+            Err(JsonError::TooManyMembers(2, Position::new(17, 1, 18))),
+            parse(r#"{"a": 1, "b": 2, "c": 3}"#)
+        );
+    }
+
+    #[test]
+    fn it_should_parse_deep_documents_on_a_small_stack() {
+        let depth = 100_000;
+        let json = format!("{}null{}", "[{\"a\": ".repeat(depth), "}]".repeat(depth));
+        let options = ParserOptions {
+            max_depth: None,
+            ..ParserOptions::default()
+        };
+
+        std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn(move || {
+                let value = Parser::new(Tokenizer::with_options(&json, options))
+                    .parse_tokens()
+                    .unwrap();
+                let copy = value.clone();
+                assert_eq!(value, copy);
+                value.drop_iteratively();
+                copy.drop_iteratively();
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn it_should_parse_json5_documents() {
+        let json = r#"// settings
+{
+    unquoted: 'single quoted',
+    "trailing": [1, 0x10, +2, .5, Infinity,],
+    /* nested */ nested: {a: null,},
+}"#;
+        let options = ParserOptions {
+            json5: true,
+            ..ParserOptions::default()
+        };
+        let json5 = Parser::new(Tokenizer::with_options(json, options.clone()))
+            .parse_tokens()
+            .unwrap();
+
+        assert_eq!(
+            JsonType::Str("single quoted".to_string()),
+            json5["unquoted"]
+        );
+        assert_eq!(
+            JsonType::Array(vec![
+                JsonType::Num(Num::Integer(1)),
+                JsonType::Num(Num::Integer(16)),
+                JsonType::Num(Num::Integer(2)),
+                JsonType::Num(Num::Float(0.5)),
+                JsonType::Num(Num::Float(f64::INFINITY)),
+            ]),
+            json5["trailing"]
+        );
+        assert_eq!(JsonType::Null, json5["nested"]["a"]);
+
+        assert!(Parser::new(Tokenizer::new(json)).parse_tokens().is_err());
+        for invalid in ["[1,,]", "[,]", "{,}", "{a: 1,,}", "[1,}", "{a}", "null"] {
+            let result =
+                Parser::new(Tokenizer::with_options(invalid, options.clone())).parse_tokens();
+            assert_eq!(invalid == "null", result.is_ok(), "{}", invalid);
+        }
+        assert_eq!(
+            Err(JsonError::ValueError(
+                Token::Identifier("key".to_string()),
+                Position::new(1, 1, 2)
+            )),
+            Parser::new(Tokenizer::with_options("[key]", options)).parse_tokens()
+        );
+    }
 }
@@ -0,0 +1,600 @@
+//! Parsing which goes on after errors, see [`Parser::parse_recovering`]
+
+use crate::error::JsonError;
+use crate::options::DuplicateKeys;
+use crate::token::span::Span;
+use crate::token::tokenizer::{Position, SpannedToken, StrSource, Token, Tokenizer};
+use crate::{JsonType, Object};
+
+use super::{Parser, scalar};
+
+/// An error found by [`Parser::parse_recovering`], along with the part of
+/// the input it is about
+#[derive(Debug, PartialEq)]
+pub struct Diagnostic {
+    pub error: JsonError,
+    pub span: Span,
+}
+
+/// What the next token should be
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expect {
+    Value,
+    Key,
+    Colon,
+    CommaOrEnd,
+}
+
+/// An array or an object being parsed
+///
+/// `key` is the key of the member being read, along with its span, `None`
+/// when the member is missing its key or is dropped.
+enum Container {
+    Array(Vec<JsonType>),
+    Object {
+        object: Object,
+        key: Option<(String, Span)>,
+        members: usize,
+    },
+}
+
+struct Recovery<'t, 'a> {
+    tokenizer: &'t mut Tokenizer<StrSource<'a>>,
+    stack: Vec<Container>,
+    expect: Expect,
+    /// The comma read last, while the item after it is expected
+    comma: Option<Span>,
+    /// How deep the tokens read are inside a container skipped for exceeding
+    /// the depth limit
+    skipped: usize,
+    root: Option<JsonType>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Parser<'_> {
+    /// Parses a single JSON value, going on after errors to report them all
+    ///
+    /// Returns what could be read of the value along with the errors in input
+    /// order, the first one being the error [`parse_tokens`](Parser::parse_tokens)
+    /// returns. After an error, parsing resumes at the next comma or bracket:
+    /// a malformed value is read as `Null`, a member missing its key or its
+    /// value is dropped, a missing comma is assumed and a closing bracket
+    /// also closes the containers left open inside the one it matches.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use json_parser::parser::Parser;
+    /// use json_parser::token::tokenizer::Tokenizer;
+    /// use json_parser::JsonType;
+    ///
+    /// let mut parser = Parser::new(Tokenizer::new(r#"{"a": tru, "b": [1 2], "c": null"#));
+    /// let (value, diagnostics) = parser.parse_recovering();
+    ///
+    /// assert_eq!(3, diagnostics.len());
+    /// assert_eq!(JsonType::Null, value["a"]);
+    /// assert_eq!(2, value["b"].as_array().unwrap().len());
+    /// assert!(value["c"].is_null());
+    /// ```
+    pub fn parse_recovering(&mut self) -> (JsonType, Vec<Diagnostic>) {
+        Recovery {
+            tokenizer: &mut self.tokenizer,
+            stack: Vec::new(),
+            expect: Expect::Value,
+            comma: None,
+            skipped: 0,
+            root: None,
+            diagnostics: Vec::new(),
+        }
+        .run()
+    }
+}
+
+impl Recovery<'_, '_> {
+    fn run(mut self) -> (JsonType, Vec<Diagnostic>) {
+        loop {
+            let before = self.position();
+            let token = match self.tokenizer.next() {
+                None => break,
+                Some(Ok(token)) => token,
+                Some(Err(error)) => {
+                    let end = self.position();
+                    let start = error.position().unwrap_or(before);
+                    self.report(error, Span::new(start, end));
+                    // The tokenizer returns the same error until the input
+                    // moves past it, which does not happen at its end
+                    if end == before {
+                        break;
+                    }
+                    self.malformed();
+                    continue;
+                }
+            };
+            if self.root.is_some() {
+                let SpannedToken { token, span } = token;
+                self.report(JsonError::TrailingToken(token, span.start), span);
+                break;
+            }
+            self.token(token);
+        }
+        self.finish()
+    }
+
+    fn report(&mut self, error: JsonError, span: Span) {
+        self.diagnostics.push(Diagnostic { error, span });
+    }
+
+    fn position(&self) -> Position {
+        Tokenizer::position(self.tokenizer)
+    }
+
+    fn json5(&self) -> bool {
+        self.tokenizer.options().json5
+    }
+
+    fn token(&mut self, SpannedToken { token, span }: SpannedToken) {
+        if self.skipped > 0 {
+            match token {
+                Token::OpenCurlybracket | Token::OpenBracket => self.skipped += 1,
+                Token::CloseCurlybracket | Token::CloseBracket => {
+                    self.skipped -= 1;
+                    if self.skipped == 0 {
+                        self.value(JsonType::Null);
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+        match self.expect {
+            Expect::Value => self.expect_value(token, span),
+            Expect::Key => self.expect_key(token, span),
+            Expect::Colon => self.expect_colon(token, span),
+            Expect::CommaOrEnd => self.expect_comma_or_end(token, span),
+        }
+    }
+
+    /// Stands for a token the tokenizer could not read
+    fn malformed(&mut self) {
+        if self.skipped > 0 {
+            return;
+        }
+        match self.expect {
+            Expect::Value | Expect::Colon => self.value(JsonType::Null),
+            Expect::Key => {
+                self.set_key(None);
+                self.expect = Expect::Colon;
+            }
+            Expect::CommaOrEnd => {}
+        }
+    }
+
+    fn expect_value(&mut self, token: Token, span: Span) {
+        let in_array = matches!(self.stack.last(), Some(Container::Array(_)));
+        match token {
+            Token::OpenCurlybracket => self.open(false, span),
+            Token::OpenBracket => self.open(true, span),
+            Token::CloseBracket if in_array => {
+                if self.comma.is_some() && !self.json5() {
+                    self.report(JsonError::ValueError(token, span.start), span);
+                }
+                self.close();
+            }
+            Token::CloseCurlybracket if in_array && self.comma.is_some() => {
+                let comma = self.comma.unwrap_or(span);
+                self.report(JsonError::InvalidComaEndObjectError(comma.start), comma);
+                self.close_to(false);
+            }
+            Token::CloseBracket | Token::CloseCurlybracket => {
+                let array = token == Token::CloseBracket;
+                self.report(JsonError::ValueError(token, span.start), span);
+                self.close_to(array);
+            }
+            Token::Comma => {
+                self.report(JsonError::ValueError(token, span.start), span);
+                if !in_array && !self.stack.is_empty() {
+                    self.set_key(None);
+                    self.expect = Expect::Key;
+                }
+                self.comma = Some(span);
+            }
+            Token::Colon => self.report(JsonError::ValueError(token, span.start), span),
+            token => match scalar(token, span.start) {
+                Ok(value) => self.value(value),
+                Err(error) => {
+                    self.report(error, span);
+                    self.value(JsonType::Null);
+                }
+            },
+        }
+    }
+
+    fn expect_key(&mut self, token: Token, span: Span) {
+        match token {
+            Token::Str(key) | Token::Identifier(key) => {
+                let mut key = Some((key, span));
+                let members = match self.stack.last_mut() {
+                    Some(Container::Object { members, .. }) => {
+                        *members += 1;
+                        *members
+                    }
+                    _ => 0,
+                };
+                if let Some(max) = self.tokenizer.options().max_object_members
+                    && members > max
+                {
+                    // Reported once, the members after the limit are dropped
+                    if members == max + 1 {
+                        self.report(JsonError::TooManyMembers(max, span.start), span);
+                    }
+                    key = None;
+                }
+                self.set_key(key);
+                self.expect = Expect::Colon;
+            }
+            Token::CloseCurlybracket => {
+                if let Some(comma) = self.comma
+                    && !self.json5()
+                {
+                    self.report(JsonError::InvalidComaEndObjectError(comma.start), comma);
+                }
+                self.close();
+            }
+            Token::Comma => {
+                self.report(JsonError::KeyError(token, span.start), span);
+                self.comma = Some(span);
+            }
+            token => {
+                self.report(JsonError::KeyError(token.clone(), span.start), span);
+                self.set_key(None);
+                match token {
+                    Token::Colon => self.expect = Expect::Value,
+                    Token::CloseBracket => self.close_to(true),
+                    Token::OpenCurlybracket | Token::OpenBracket => self.expect_value(token, span),
+                    _ => self.expect = Expect::Colon,
+                }
+            }
+        }
+    }
+
+    fn expect_colon(&mut self, token: Token, span: Span) {
+        if token == Token::Colon {
+            self.expect = Expect::Value;
+            return;
+        }
+        self.report(JsonError::CollonError(token.clone(), span.start), span);
+        match token {
+            Token::Comma => {
+                self.set_key(None);
+                self.comma = Some(span);
+                self.expect = Expect::Key;
+            }
+            Token::CloseCurlybracket => self.close(),
+            Token::CloseBracket => self.close_to(true),
+            // The colon is missing, the token is read as the value
+            token => self.expect_value(token, span),
+        }
+    }
+
+    fn expect_comma_or_end(&mut self, token: Token, span: Span) {
+        let in_array = matches!(self.stack.last(), Some(Container::Array(_)));
+        match token {
+            Token::Comma => {
+                self.comma = Some(span);
+                self.expect = if in_array { Expect::Value } else { Expect::Key };
+            }
+            Token::CloseBracket if in_array => self.close(),
+            Token::CloseCurlybracket if !in_array => self.close(),
+            Token::CloseBracket | Token::CloseCurlybracket => {
+                let array = token == Token::CloseBracket;
+                self.report(JsonError::EndObjectError(token, span.start), span);
+                self.close_to(array);
+            }
+            Token::Colon => self.report(JsonError::EndObjectError(token, span.start), span),
+            // The comma is missing, the token starts the next item
+            token => {
+                self.report(JsonError::EndObjectError(token.clone(), span.start), span);
+                if in_array {
+                    self.expect_value(token, span);
+                } else {
+                    self.expect_key(token, span);
+                }
+            }
+        }
+    }
+
+    fn open(&mut self, array: bool, span: Span) {
+        if let Some(max) = self.tokenizer.options().max_depth
+            && self.stack.len() >= max
+        {
+            self.report(JsonError::DepthLimitExceeded(max, span.start), span);
+            self.skipped = 1;
+            return;
+        }
+        if array {
+            self.stack.push(Container::Array(Vec::new()));
+            self.expect = Expect::Value;
+        } else {
+            self.stack.push(Container::Object {
+                object: Object::default(),
+                key: None,
+                members: 0,
+            });
+            self.expect = Expect::Key;
+        }
+        self.comma = None;
+    }
+
+    fn set_key(&mut self, new_key: Option<(String, Span)>) {
+        if let Some(Container::Object { key, .. }) = self.stack.last_mut() {
+            *key = new_key;
+        }
+    }
+
+    /// Adds a complete value to the innermost container, or makes it the
+    /// document when there is none
+    fn value(&mut self, value: JsonType) {
+        self.comma = None;
+        self.expect = Expect::CommaOrEnd;
+        let duplicate_keys = self.tokenizer.options().duplicate_keys;
+        let (object, key, span) = match self.stack.last_mut() {
+            None => {
+                self.root = Some(value);
+                return;
+            }
+            Some(Container::Array(values)) => {
+                values.push(value);
+                return;
+            }
+            Some(Container::Object { object, key, .. }) => match key.take() {
+                Some((key, span)) => (object, key, span),
+                None => return,
+            },
+        };
+        if !object.contains_key(&key) {
+            object.insert(key, value);
+            return;
+        }
+        match duplicate_keys {
+            DuplicateKeys::First => {}
+            DuplicateKeys::Last => {
+                object.insert(key, value);
+            }
+            DuplicateKeys::Error => self.report(JsonError::DuplicateKey(key, span.start), span),
+        }
+    }
+
+    /// Closes the innermost container, dropping a member left without value
+    fn close(&mut self) {
+        let value = match self.stack.pop() {
+            Some(Container::Array(values)) => JsonType::Array(values),
+            Some(Container::Object { object, .. }) => JsonType::Object(Box::new(object)),
+            None => return,
+        };
+        self.value(value);
+    }
+
+    /// Handles a closing bracket which does not match the innermost
+    /// container: it closes the containers up to the one it matches, or is
+    /// ignored when none does
+    fn close_to(&mut self, array: bool) {
+        let matching = self
+            .stack
+            .iter()
+            .rposition(|container| matches!(container, Container::Array(_)) == array);
+        if let Some(depth) = matching {
+            while self.stack.len() > depth {
+                self.close();
+            }
+        }
+    }
+
+    fn finish(mut self) -> (JsonType, Vec<Diagnostic>) {
+        let end = self.position();
+        let span = Span::new(end, end);
+        if self.skipped > 0 || !self.stack.is_empty() {
+            // An error running up to the end of the input already says so
+            if !self
+                .diagnostics
+                .last()
+                .is_some_and(|last| last.span.end == end)
+            {
+                self.report(JsonError::UnexpectedEndOfJson(end), span);
+            }
+            if self.skipped > 0 {
+                self.skipped = 0;
+                self.value(JsonType::Null);
+            }
+            while !self.stack.is_empty() {
+                self.close();
+            }
+        } else if self.root.is_none() && self.diagnostics.is_empty() {
+            self.report(JsonError::EmptyInput(end), span);
+        }
+        (self.root.unwrap_or(JsonType::Null), self.diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::ParserOptions;
+    use crate::token::span::Position;
+    use crate::types::Num;
+
+    fn recover(json: &str) -> (JsonType, Vec<Diagnostic>) {
+        Parser::new(Tokenizer::new(json)).parse_recovering()
+    }
+
+    fn errors(json: &str) -> Vec<JsonError> {
+        let (_, diagnostics) = recover(json);
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.error)
+            .collect()
+    }
+
+    fn number(value: i64) -> JsonType {
+        JsonType::Num(Num::Integer(value))
+    }
+
+    #[test]
+    fn it_should_parse_valid_documents_without_diagnostics() {
+        for json in [r#"{"a": [1, {"b": null}], "c": "d"}"#, "[]", "{}", " 12 "] {
+            let expected = Parser::new(Tokenizer::new(json)).parse_tokens().unwrap();
+            assert_eq!((expected, Vec::new()), recover(json), "{}", json);
+        }
+    }
+
+    #[test]
+    fn it_should_report_first_the_error_the_parser_returns() {
+        for json in [
+            "",
+            "[1, 2",
+            "[1,]",
+            "[1,}",
+            "[,1]",
+            "[1 2]",
+            "{\"a\": 1,}",
+            "{\"a\" 1}",
+            "{1: 2}",
+            "{\"a\": }",
+            "{\"a\": 1 \"b\": 2}",
+            "[1] 2",
+            "[tru]",
+            "[\"\\q\"]",
+            "[1}",
+            "{\"a\": [}",
+        ] {
+            let error = Parser::new(Tokenizer::new(json))
+                .parse_tokens()
+                .unwrap_err();
+            assert_eq!(Some(&error), errors(json).first(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn it_should_collect_every_error() {
+        let json =
+            "{\n  \"a\": tru,\n  \"b\": [1,, 2 3],\n  \"c\" 4,\n  5: 6,\n  \"d\": \"\\x\" \n}";
+        let (value, diagnostics) = recover(json);
+
+        assert_eq!(
+            vec![
+                Diagnostic {
+                    error: JsonError::InvalidLiteral("tru".to_string(), Position::new(9, 2, 8)),
+                    span: Span::new(Position::new(9, 2, 8), Position::new(12, 2, 11)),
+                },
+                Diagnostic {
+                    error: JsonError::ValueError(Token::Comma, Position::new(24, 3, 11)),
+                    span: Span::new(Position::new(24, 3, 11), Position::new(25, 3, 12)),
+                },
+                Diagnostic {
+                    error: JsonError::EndObjectError(Token::Int(3), Position::new(28, 3, 15)),
+                    span: Span::new(Position::new(28, 3, 15), Position::new(29, 3, 16)),
+                },
+                Diagnostic {
+                    error: JsonError::CollonError(Token::Int(4), Position::new(38, 4, 7)),
+                    span: Span::new(Position::new(38, 4, 7), Position::new(39, 4, 8)),
+                },
+                Diagnostic {
+                    error: JsonError::KeyError(Token::Int(5), Position::new(43, 5, 3)),
+                    span: Span::new(Position::new(43, 5, 3), Position::new(44, 5, 4)),
+                },
+                Diagnostic {
+                    error: JsonError::InvalidEscape('x', Position::new(57, 6, 9)),
+                    span: Span::new(Position::new(57, 6, 9), Position::new(60, 6, 12)),
+                },
+            ],
+            diagnostics
+        );
+        assert_eq!(JsonType::Null, value["a"]);
+        assert_eq!(
+            JsonType::Array(vec![number(1), number(2), number(3)]),
+            value["b"]
+        );
+        assert_eq!(number(4), value["c"]);
+        assert_eq!(JsonType::Null, value["d"]);
+        assert_eq!(4, value.as_object().unwrap().len());
+    }
+
+    #[test]
+    fn it_should_resume_after_a_string_cut_by_a_newline() {
+        let (value, diagnostics) = recover("[\"abc\n, 1]");
+
+        assert_eq!(
+            vec![Diagnostic {
+                error: JsonError::ControlCharacter('\n', Position::new(5, 1, 6)),
+                span: Span::new(Position::new(5, 1, 6), Position::new(6, 2, 1)),
+            }],
+            diagnostics
+        );
+        assert_eq!(
+            JsonType::Array(vec![JsonType::Null, JsonType::Num(Num::Integer(1))]),
+            value
+        );
+    }
+
+    #[test]
+    fn it_should_close_containers_left_open() {
+        let (value, diagnostics) = recover(r#"{"a": [1, {"b": 2], "c": [3"#);
+
+        assert_eq!(
+            vec![
+                JsonError::EndObjectError(Token::CloseBracket, Position::new(17, 1, 18)),
+                JsonError::UnexpectedEndOfJson(Position::new(27, 1, 28)),
+            ],
+            diagnostics
+                .into_iter()
+                .map(|diagnostic| diagnostic.error)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(&number(2)), value.pointer("/a/1/b"));
+        assert_eq!(Some(&number(3)), value.pointer("/c/0"));
+    }
+
+    #[test]
+    fn it_should_report_limits_and_go_on() {
+        let options = ParserOptions {
+            max_depth: Some(2),
+            max_object_members: Some(1),
+            ..ParserOptions::default()
+        };
+        let json = r#"[[[1, [2]], 3], {"a": 1, "b": 2, "c": 3}]"#;
+        let (value, diagnostics) =
+            Parser::new(Tokenizer::with_options(json, options)).parse_recovering();
+
+        assert_eq!(
+            vec![
+                JsonError::DepthLimitExceeded(2, Position::new(2, 1, 3)),
+                JsonError::TooManyMembers(1, Position::new(25, 1, 26)),
+            ],
+            diagnostics
+                .into_iter()
+                .map(|diagnostic| diagnostic.error)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(&JsonType::Null), value.pointer("/0/0"));
+        assert_eq!(Some(&number(3)), value.pointer("/0/1"));
+        assert_eq!(1, value[1].as_object().unwrap().len());
+    }
+
+    #[test]
+    fn it_should_stop_after_a_trailing_token() {
+        assert_eq!(
+            vec![JsonError::TrailingToken(
+                Token::Int(2),
+                Position::new(4, 1, 5)
+            )],
+            errors("[1] 2 3 4")
+        );
+        assert_eq!(
+            vec![JsonError::EmptyInput(Position::new(2, 1, 3))],
+            errors("  ")
+        );
+        assert_eq!(
+            vec![JsonError::UnterminatedString(Position::new(1, 1, 2))],
+            errors("[\"open")
+        );
+    }
+}
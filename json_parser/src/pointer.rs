@@ -0,0 +1,325 @@
+//! JSON Pointer (RFC 6901) navigation in a [`JsonType`]
+//!
+//! A pointer is either empty, referring to the whole document, or a sequence
+//! of `/` prefixed reference tokens where `~1` stands for `/` and `~0` for `~`.
+//!
+//! # Examples
+//!
+//! ```
+//! use json_parser::{from_string, JsonType};
+//!
+//! let mut json: JsonType = from_string(r#"{"users": [{"name": "Alice"}], "a/b": 1}"#).unwrap();
+//!
+//! assert_eq!(Some(&JsonType::Str("Alice".to_string())), json.pointer("/users/0/name"));
+//! assert!(json.pointer("/a~1b").is_some());
+//!
+//! json.insert_at("/users/-", JsonType::Null).unwrap();
+//! assert_eq!(Ok(JsonType::Null), json.remove_at("/users/1"));
+//! ```
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::JsonType;
+
+/// Errors returned when a pointer cannot be followed
+#[derive(Debug, PartialEq, Clone)]
+pub enum PointerError {
+    /// A non-empty pointer does not start with `/`
+    MissingSlash(String),
+    /// A `~` is not followed by `0` or `1`
+    InvalidEscape(String),
+    /// A reference token into an array is not an index or is out of bounds
+    InvalidIndex(String),
+    /// The pointer goes through a member which does not exist or a value
+    /// which is neither an object nor an array
+    NotFound(String),
+    /// A value cannot be written where the pointer refers to, like a
+    /// non-finite float in a document which is not JSON5
+    InvalidValue(String),
+}
+
+impl Display for PointerError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            PointerError::MissingSlash(pointer) => {
+                write!(f, "Error: JSON pointer must start with '/': {}", pointer)
+            }
+            PointerError::InvalidEscape(pointer) => {
+                write!(f, "Error: invalid escape in JSON pointer: {}", pointer)
+            }
+            PointerError::InvalidIndex(token) => write!(f, "Error: invalid array index: {}", token),
+            PointerError::NotFound(pointer) => {
+                write!(f, "Error: JSON pointer does not match a value: {}", pointer)
+            }
+            PointerError::InvalidValue(reason) => {
+                write!(f, "Error: value cannot be written: {}", reason)
+            }
+        }
+    }
+}
+
+impl Error for PointerError {}
+
+/// Splits `pointer` into its unescaped reference tokens
+pub(crate) fn parse(pointer: &str) -> Result<Vec<String>, PointerError> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(tokens) = pointer.strip_prefix('/') else {
+        return Err(PointerError::MissingSlash(pointer.to_string()));
+    };
+    tokens
+        .split('/')
+        .map(|token| {
+            let mut unescaped = String::with_capacity(token.len());
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                if c != '~' {
+                    unescaped.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('0') => unescaped.push('~'),
+                    Some('1') => unescaped.push('/'),
+                    _ => return Err(PointerError::InvalidEscape(pointer.to_string())),
+                }
+            }
+            Ok(unescaped)
+        })
+        .collect()
+}
+
+/// Reads an array index, which has no sign and no leading zero
+pub(crate) fn index(token: &str) -> Result<usize, PointerError> {
+    let digits = !token.is_empty() && token.bytes().all(|b| b.is_ascii_digit());
+    if !digits || (token.len() > 1 && token.starts_with('0')) {
+        return Err(PointerError::InvalidIndex(token.to_string()));
+    }
+    token
+        .parse()
+        .map_err(|_| PointerError::InvalidIndex(token.to_string()))
+}
+
+/// Follows `token` one level down into `value`
+fn child<'a>(value: &'a mut JsonType, token: &str) -> Option<&'a mut JsonType> {
+    match value {
+        JsonType::Object(object) => object.get_mut(token),
+        JsonType::Array(values) => values.get_mut(index(token).ok()?),
+        _ => None,
+    }
+}
+
+impl JsonType {
+    /// Returns the value `pointer` refers to, `None` if there is none or the
+    /// pointer is invalid
+    pub fn pointer(&self, pointer: &str) -> Option<&JsonType> {
+        let mut value = self;
+        for token in parse(pointer).ok()? {
+            value = match value {
+                JsonType::Object(object) => object.get(&token)?,
+                JsonType::Array(values) => values.get(index(&token).ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// Same as [`pointer`](JsonType::pointer) returning a mutable reference
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JsonType> {
+        let mut value = self;
+        for token in parse(pointer).ok()? {
+            value = child(value, &token)?;
+        }
+        Some(value)
+    }
+
+    /// Adds `new_value` where `pointer` refers to, like the `add` operation
+    /// of JSON Patch
+    ///
+    /// An object member is inserted or replaced, an array element is inserted
+    /// before the one at the index, or appended for `-`, and the empty pointer
+    /// replaces the whole value. Returns the replaced value, if any.
+    pub fn insert_at(
+        &mut self,
+        pointer: &str,
+        new_value: JsonType,
+    ) -> Result<Option<JsonType>, PointerError> {
+        let mut tokens = parse(pointer)?;
+        let Some(last) = tokens.pop() else {
+            return Ok(Some(std::mem::replace(self, new_value)));
+        };
+        let mut parent = self;
+        for token in &tokens {
+            parent =
+                child(parent, token).ok_or_else(|| PointerError::NotFound(pointer.to_string()))?;
+        }
+        match parent {
+            JsonType::Object(object) => Ok(object.insert(last, new_value)),
+            JsonType::Array(values) => {
+                let index = match last.as_str() {
+                    "-" => values.len(),
+                    token => index(token)?,
+                };
+                if index > values.len() {
+                    return Err(PointerError::InvalidIndex(last));
+                }
+                values.insert(index, new_value);
+                Ok(None)
+            }
+            _ => Err(PointerError::NotFound(pointer.to_string())),
+        }
+    }
+
+    /// Removes and returns the value `pointer` refers to
+    ///
+    /// The empty pointer takes the whole value, leaving `Null` in its place.
+    pub fn remove_at(&mut self, pointer: &str) -> Result<JsonType, PointerError> {
+        let mut tokens = parse(pointer)?;
+        let Some(last) = tokens.pop() else {
+            return Ok(std::mem::replace(self, JsonType::Null));
+        };
+        let mut parent = self;
+        for token in &tokens {
+            parent =
+                child(parent, token).ok_or_else(|| PointerError::NotFound(pointer.to_string()))?;
+        }
+        match parent {
+            JsonType::Object(object) => object
+                .remove(&last)
+                .ok_or_else(|| PointerError::NotFound(pointer.to_string())),
+            JsonType::Array(values) => {
+                let index = index(&last)?;
+                if index >= values.len() {
+                    return Err(PointerError::InvalidIndex(last));
+                }
+                Ok(values.remove(index))
+            }
+            _ => Err(PointerError::NotFound(pointer.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_string;
+    use crate::types::Num;
+
+    fn document() -> JsonType {
+        from_string(
+            r#"{"users": [{"name": "Alice"}, {"name": "Bob"}], "a/b": 1, "m~n": 2, "": 3, "0": 4}"#,
+        )
+        .unwrap()
+    }
+
+    fn string(value: &str) -> JsonType {
+        JsonType::Str(value.to_string())
+    }
+
+    #[test]
+    fn it_should_follow_pointers() {
+        let json = document();
+
+        assert_eq!(Some(&json), json.pointer(""));
+        assert_eq!(Some(&string("Bob")), json.pointer("/users/1/name"));
+        assert!(json.pointer("/a~1b").is_some());
+        assert!(json.pointer("/m~0n").is_some());
+        assert!(json.pointer("/").is_some());
+        assert!(json.pointer("/0").is_some());
+    }
+
+    #[test]
+    fn it_should_return_none_on_a_miss() {
+        let json = document();
+
+        for pointer in [
+            "users",
+            "/missing",
+            "/users/2",
+            "/users/01",
+            "/users/-",
+            "/users/+1",
+            "/users/0/name/first",
+            "/a~2b",
+        ] {
+            assert_eq!(None, json.pointer(pointer), "{}", pointer);
+        }
+    }
+
+    #[test]
+    fn it_should_mutate_through_a_pointer() {
+        let mut json = document();
+        *json.pointer_mut("/users/0/name").unwrap() = string("Carol");
+
+        assert_eq!(Some(&string("Carol")), json.pointer("/users/0/name"));
+        assert_eq!(None, json.pointer_mut("/users/9"));
+    }
+
+    #[test]
+    fn it_should_insert_at_a_pointer() {
+        let mut json = document();
+
+        assert_eq!(Ok(None), json.insert_at("/users/0/age", JsonType::Null));
+        assert_eq!(
+            Ok(Some(string("Alice"))),
+            json.insert_at("/users/0/name", string("Ann"))
+        );
+        assert_eq!(Ok(None), json.insert_at("/users/0", string("first")));
+        assert_eq!(Ok(None), json.insert_at("/users/-", string("last")));
+        assert_eq!(Some(&string("first")), json.pointer("/users/0"));
+        assert_eq!(Some(&string("last")), json.pointer("/users/3"));
+        assert_eq!(
+            Err(PointerError::InvalidIndex("9".to_string())),
+            json.insert_at("/users/9", JsonType::Null)
+        );
+        assert_eq!(
+            Err(PointerError::NotFound("/missing/key".to_string())),
+            json.insert_at("/missing/key", JsonType::Null)
+        );
+        assert_eq!(
+            Err(PointerError::NotFound("/a~1b/c".to_string())),
+            json.insert_at("/a~1b/c", JsonType::Null)
+        );
+
+        let before = json.clone();
+        assert_eq!(Ok(Some(before)), json.insert_at("", JsonType::Null));
+        assert_eq!(JsonType::Null, json);
+    }
+
+    #[test]
+    fn it_should_remove_at_a_pointer() {
+        let mut json = document();
+
+        assert_eq!(Ok(string("Alice")), json.remove_at("/users/0/name"));
+        assert_eq!(Ok(JsonType::Num(Num::Integer(1))), json.remove_at("/a~1b"));
+        assert!(json.remove_at("/users/0").is_ok());
+        assert_eq!(Some(&string("Bob")), json.pointer("/users/0/name"));
+        assert_eq!(
+            Err(PointerError::InvalidIndex("1".to_string())),
+            json.remove_at("/users/1")
+        );
+        assert_eq!(
+            Err(PointerError::NotFound("/a~1b".to_string())),
+            json.remove_at("/a~1b")
+        );
+    }
+
+    #[test]
+    fn it_should_reject_invalid_pointers() {
+        let mut json = document();
+
+        assert_eq!(
+            Err(PointerError::MissingSlash("users".to_string())),
+            json.remove_at("users")
+        );
+        assert_eq!(
+            Err(PointerError::InvalidEscape("/a~".to_string())),
+            json.insert_at("/a~", JsonType::Null)
+        );
+        assert_eq!(
+            Err(PointerError::InvalidIndex("-".to_string())),
+            json.remove_at("/users/-")
+        );
+    }
+}
@@ -0,0 +1,267 @@
+//! Helpers called by the code generated by the `Serialize` and `Deserialize`
+//! derive macros. Not part of the public API.
+
+use crate::de::{Deserializer, FromDeserializer, Visitor};
+use crate::error::JsonError;
+use crate::serializer::SerializeMap;
+use crate::types::Object;
+use crate::{Deserialize, JsonType};
+
+/// Called when a field is absent from the object
+///
+/// Types accepting `null`, like `Option`, are built from it so optional
+/// fields can be omitted.
+pub fn missing_field<T: Deserialize>(type_name: &str, field: &str) -> Result<T, JsonError> {
+    T::deserialize(JsonType::Null).map_err(|_| {
+        JsonError::DeserializationError(format!("missing field `{}` in {}", field, type_name))
+    })
+}
+
+/// Same as [`missing_field`] for the visitor based [`FromDeserializer`]
+pub fn missing_visited_field<'de, T: FromDeserializer<'de>>(
+    type_name: &str,
+    field: &str,
+) -> Result<T, JsonError> {
+    T::from_deserializer(NullDeserializer).map_err(|_| {
+        JsonError::DeserializationError(format!("missing field `{}` in {}", field, type_name))
+    })
+}
+
+/// Hands `null` to any visitor
+struct NullDeserializer;
+
+impl<'de> Deserializer<'de> for NullDeserializer {
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        visitor.visit_null()
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        visitor.visit_null()
+    }
+}
+
+pub fn invalid_type(expected: &str, type_name: &str, got: &JsonType) -> JsonError {
+    JsonError::DeserializationError(format!(
+        "expected {} for {}, got {}",
+        expected,
+        type_name,
+        describe(got)
+    ))
+}
+
+pub fn invalid_value(expected: &str, type_name: &str) -> JsonError {
+    JsonError::DeserializationError(format!("expected {} for {}", expected, type_name))
+}
+
+pub fn invalid_length(expected: usize, got: usize, type_name: &str) -> JsonError {
+    JsonError::DeserializationError(format!(
+        "expected an array of {} elements for {}, got {}",
+        expected, type_name, got
+    ))
+}
+
+pub fn unknown_field(field: &str, type_name: &str) -> JsonError {
+    JsonError::DeserializationError(format!("unknown field `{}` in {}", field, type_name))
+}
+
+pub fn unknown_variant(variant: &str, type_name: &str) -> JsonError {
+    JsonError::DeserializationError(format!("unknown variant `{}` for {}", variant, type_name))
+}
+
+/// Merges a `flatten` field into the members of its parent object
+///
+/// Objects are merged, `null` adds nothing and any other value is kept under
+/// the name of the field.
+pub fn flatten_into(entries: &mut Vec<(String, JsonType)>, field: &str, value: JsonType) {
+    match value {
+        JsonType::Object(object) => entries.extend(*object),
+        JsonType::Null => {}
+        value => entries.push((field.to_string(), value)),
+    }
+}
+
+/// Streaming counterpart of [`flatten_into`], writing the members to `map`
+pub fn flatten_into_map<M: SerializeMap>(
+    map: &mut M,
+    field: &str,
+    value: JsonType,
+) -> Result<(), JsonError> {
+    match value {
+        JsonType::Object(object) => {
+            for (key, value) in object.iter() {
+                map.serialize_entry(key, value)?;
+            }
+            Ok(())
+        }
+        JsonType::Null => Ok(()),
+        value => map.serialize_entry(field, &value),
+    }
+}
+
+/// Adds the tag of an internally tagged variant in front of its content
+///
+/// # Panics
+///
+/// Panics if the content is neither an object nor `null`, as the tag cannot
+/// be stored next to it.
+pub fn tag_content(tag: &str, variant: &str, content: JsonType) -> JsonType {
+    let entry = (tag.to_string(), JsonType::Str(variant.to_string()));
+    let object = match content {
+        JsonType::Object(object) => *object,
+        JsonType::Null => Object::default(),
+        content => panic!(
+            "cannot serialize {} as the content of internally tagged variant `{}`",
+            describe(&content),
+            variant
+        ),
+    };
+    let object = std::iter::once(entry)
+        .chain(object.into_iter().filter(|(key, _)| key != tag))
+        .collect();
+    JsonType::Object(Box::new(object))
+}
+
+/// Streaming counterpart of [`tag_content`], writing the members of the
+/// content to `map` after the tag
+///
+/// Content that is neither an object nor `null` is reported as an error
+/// rather than a panic.
+pub fn tag_content_into_map<M: SerializeMap>(
+    map: &mut M,
+    tag: &str,
+    variant: &str,
+    content: JsonType,
+) -> Result<(), JsonError> {
+    map.serialize_entry(tag, variant)?;
+    match &content {
+        JsonType::Object(object) => {
+            for (key, value) in object.iter().filter(|(key, _)| *key != tag) {
+                map.serialize_entry(key, value)?;
+            }
+            Ok(())
+        }
+        JsonType::Null => Ok(()),
+        content => Err(JsonError::SerializationError(format!(
+            "cannot serialize {} as the content of internally tagged variant `{}`",
+            describe(content),
+            variant
+        ))),
+    }
+}
+
+/// Splits an internally tagged object into its variant name and the object
+/// holding the remaining members
+pub fn take_tag(
+    object: Object,
+    tag: &str,
+    type_name: &str,
+) -> Result<(String, JsonType), JsonError> {
+    let mut variant = None;
+    let mut rest = Vec::new();
+    for (key, value) in object {
+        if variant.is_none() && key == tag {
+            variant = Some(value);
+        } else {
+            rest.push((key, value));
+        }
+    }
+    let variant = variant_name(variant, tag, type_name)?;
+    Ok((variant, JsonType::Object(Box::new(Object::new(rest)))))
+}
+
+/// Splits an adjacently tagged object into its variant name and its content
+///
+/// A missing content is read as `null`, other members are ignored.
+pub fn take_adjacent(
+    object: Object,
+    tag: &str,
+    content: &str,
+    type_name: &str,
+) -> Result<(String, JsonType), JsonError> {
+    let mut variant = None;
+    let mut value = JsonType::Null;
+    for (key, member) in object {
+        if key == tag {
+            variant = Some(member);
+        } else if key == content {
+            value = member;
+        }
+    }
+    let variant = variant_name(variant, tag, type_name)?;
+    Ok((variant, value))
+}
+
+fn variant_name(tag: Option<JsonType>, key: &str, type_name: &str) -> Result<String, JsonError> {
+    match tag {
+        Some(JsonType::Str(variant)) => Ok(variant),
+        Some(other) => Err(invalid_type("a string tag", type_name, &other)),
+        None => Err(JsonError::DeserializationError(format!(
+            "missing tag `{}` in {}",
+            key, type_name
+        ))),
+    }
+}
+
+/// How close an object is to a variant with the given fields, used to pick
+/// the error reported for untagged enums
+pub fn object_score(json: &JsonType, keys: &[&str]) -> usize {
+    match json {
+        JsonType::Object(object) => {
+            let present = keys.iter().filter(|key| object.contains_key(key)).count();
+            1 + present
+        }
+        _ => 0,
+    }
+}
+
+/// How close an array is to a tuple variant of the given length
+pub fn array_score(json: &JsonType, length: usize) -> usize {
+    match json {
+        JsonType::Array(array) if array.len() == length => 2,
+        JsonType::Array(_) => 1,
+        _ => 0,
+    }
+}
+
+/// Reports that no variant of an untagged enum matched, along with the error
+/// of the closest variant
+///
+/// Each attempt holds the variant name, its score and its error. The first
+/// variant with the highest score is the closest.
+pub fn untagged_error(type_name: &str, attempts: Vec<(&str, usize, JsonError)>) -> JsonError {
+    let mut closest: Option<(&str, usize, JsonError)> = None;
+    for attempt in attempts {
+        if closest.as_ref().is_none_or(|best| attempt.1 > best.1) {
+            closest = Some(attempt);
+        }
+    }
+
+    match closest {
+        Some((variant, _, error)) => {
+            let reason = match error {
+                JsonError::DeserializationError(message) => message,
+                error => error.to_string(),
+            };
+            JsonError::DeserializationError(format!(
+                "data did not match any variant of untagged enum {}, closest variant `{}` failed: {}",
+                type_name, variant, reason
+            ))
+        }
+        None => JsonError::DeserializationError(format!(
+            "data did not match any variant of untagged enum {}",
+            type_name
+        )),
+    }
+}
+
+/// Describes the kind of a value for error messages
+pub fn describe(json: &JsonType) -> &'static str {
+    match json {
+        JsonType::Str(_) => "a string",
+        JsonType::Num(_) => "a number",
+        JsonType::Bool(_) => "a boolean",
+        JsonType::Object(_) => "an object",
+        JsonType::Array(_) => "an array",
+        JsonType::Null => "null",
+    }
+}
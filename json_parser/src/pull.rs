@@ -0,0 +1,437 @@
+//! Pull parser reading a document one event at a time
+//!
+//! Containers are reported by their start and end events and only scalars
+//! are built as [`JsonType`], so memory use stays bounded by the nesting
+//! depth whatever the size of the input.
+//!
+//! # Examples
+//!
+//! ```
+//! use json_parser::pull::{self, Event};
+//! use json_parser::JsonType;
+//! use json_parser::types::Num;
+//!
+//! let input = r#"{"ids": [1, 2]}"#.as_bytes();
+//! let events: Vec<Event> = pull::from_reader(input).collect::<Result<_, _>>().unwrap();
+//!
+//! assert_eq!(events[0], Event::StartObject);
+//! assert_eq!(events[1], Event::Key("ids".to_string()));
+//! assert_eq!(events[2], Event::StartArray);
+//! assert_eq!(events[3], Event::Value(JsonType::Num(Num::Integer(1))));
+//! ```
+
+use std::io::Read;
+
+use crate::JsonType;
+use crate::error::JsonError;
+use crate::options::ParserOptions;
+use crate::token::tokenizer::{Position, ReaderSource, Source, SpannedToken, Token, Tokenizer};
+use crate::types::Num;
+
+/// A step of the document read by a [`PullParser`]
+#[derive(Debug, PartialEq, Clone)]
+pub enum Event {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    /// The key of the next object member, its value follows
+    Key(String),
+    /// A string, number, boolean or null, never an array or an object
+    Value(JsonType),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Container {
+    Object { members: usize },
+    Array,
+}
+
+/// What the next token may be
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expect {
+    Value,
+    FirstElementOrEnd,
+    CommaOrEndArray,
+    FirstKeyOrEnd,
+    Key,
+    CommaOrEndObject,
+    /// The document is complete, only the end of the input may follow
+    Done,
+    /// The end of the input or an error was returned
+    Finished,
+}
+
+/// Reads a single JSON document as a sequence of [`Event`]s
+///
+/// Errors are the ones [`Parser`](crate::parser::Parser) returns on the same
+/// input, and no event follows an error. Every member of an object is
+/// reported, whatever [`DuplicateKeys`](crate::DuplicateKeys) says.
+#[derive(Debug)]
+pub struct PullParser<S> {
+    tokenizer: Tokenizer<S>,
+    containers: Vec<Container>,
+    expect: Expect,
+}
+
+/// Reads the document of `reader` as events
+pub fn from_reader<R: Read>(reader: R) -> PullParser<ReaderSource<R>> {
+    from_reader_with_options(reader, ParserOptions::default())
+}
+
+/// Same as [`from_reader`] with custom [`ParserOptions`]
+pub fn from_reader_with_options<R: Read>(
+    reader: R,
+    options: ParserOptions,
+) -> PullParser<ReaderSource<R>> {
+    PullParser::new(Tokenizer::from_reader_with_options(reader, options))
+}
+
+impl<S: Source> PullParser<S> {
+    pub fn new(tokenizer: Tokenizer<S>) -> Self {
+        Self {
+            tokenizer,
+            containers: Vec::new(),
+            expect: Expect::Value,
+        }
+    }
+
+    /// Returns the position of the next character to be read
+    pub fn position(&self) -> Position {
+        self.tokenizer.position()
+    }
+
+    /// Returns the number of arrays and objects the parser is inside of
+    pub fn depth(&self) -> usize {
+        self.containers.len()
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event>, JsonError> {
+        let token = self.tokenizer.next().transpose()?;
+        let Some(SpannedToken { token, span }) = token else {
+            return match self.expect {
+                Expect::Done => Ok(None),
+                Expect::Value if self.containers.is_empty() => {
+                    Err(JsonError::EmptyInput(self.tokenizer.position()))
+                }
+                _ => Err(JsonError::UnexpectedEndOfJson(self.tokenizer.position())),
+            };
+        };
+        match (self.expect, token) {
+            (Expect::Done, token) => Err(JsonError::TrailingToken(token, span.start)),
+            (Expect::FirstElementOrEnd, Token::CloseBracket) => Ok(Some(self.close())),
+            (Expect::Value | Expect::FirstElementOrEnd, token) => self.value(token, span.start),
+            (Expect::CommaOrEndArray, Token::Comma) => {
+                if self.tokenizer.is_next_token_closing_curly_bracket() {
+                    return Err(JsonError::InvalidComaEndObjectError(span.start));
+                }
+                // JSON5 allows a trailing comma, so the array may end here
+                self.expect = if self.tokenizer.options().json5 {
+                    Expect::FirstElementOrEnd
+                } else {
+                    Expect::Value
+                };
+                self.next_event()
+            }
+            (Expect::CommaOrEndArray, Token::CloseBracket) => Ok(Some(self.close())),
+            (Expect::FirstKeyOrEnd, Token::CloseCurlybracket) => Ok(Some(self.close())),
+            (Expect::FirstKeyOrEnd | Expect::Key, Token::Str(key) | Token::Identifier(key)) => {
+                if let Some(Container::Object { members }) = self.containers.last_mut() {
+                    *members += 1;
+                    if let Some(max) = self.tokenizer.options().max_object_members
+                        && *members > max
+                    {
+                        return Err(JsonError::TooManyMembers(max, span.start));
+                    }
+                }
+                self.expect_colon()?;
+                self.expect = Expect::Value;
+                Ok(Some(Event::Key(key)))
+            }
+            (Expect::FirstKeyOrEnd | Expect::Key, token) => {
+                Err(JsonError::KeyError(token, span.start))
+            }
+            (Expect::CommaOrEndObject, Token::Comma) => {
+                if self.tokenizer.options().json5 {
+                    self.expect = Expect::FirstKeyOrEnd;
+                    return self.next_event();
+                }
+                if self.tokenizer.is_next_token_closing_curly_bracket() {
+                    return Err(JsonError::InvalidComaEndObjectError(span.start));
+                }
+                self.expect = Expect::Key;
+                self.next_event()
+            }
+            (Expect::CommaOrEndObject, Token::CloseCurlybracket) => Ok(Some(self.close())),
+            (Expect::CommaOrEndArray | Expect::CommaOrEndObject, token) => {
+                Err(JsonError::EndObjectError(token, span.start))
+            }
+            (Expect::Finished, _) => Ok(None),
+        }
+    }
+
+    fn value(&mut self, token: Token, start: Position) -> Result<Option<Event>, JsonError> {
+        let value = match token {
+            Token::OpenCurlybracket => {
+                self.enter(Container::Object { members: 0 }, start)?;
+                self.expect = Expect::FirstKeyOrEnd;
+                return Ok(Some(Event::StartObject));
+            }
+            Token::OpenBracket => {
+                self.enter(Container::Array, start)?;
+                self.expect = Expect::FirstElementOrEnd;
+                return Ok(Some(Event::StartArray));
+            }
+            Token::Str(value) => JsonType::Str(value),
+            Token::Int(value) => JsonType::Num(Num::Integer(value)),
+            Token::UInt(value) => JsonType::Num(Num::UInteger(value)),
+            Token::Float(value) => JsonType::Num(Num::Float(value)),
+            Token::Decimal(value) => JsonType::Num(Num::Decimal(value)),
+            Token::Bool(value) => JsonType::Bool(value),
+            Token::Null => JsonType::Null,
+            token => return Err(JsonError::ValueError(token, start)),
+        };
+        self.end_value();
+        Ok(Some(Event::Value(value)))
+    }
+
+    /// Goes into `container`, its opening bracket being at `start`
+    fn enter(&mut self, container: Container, start: Position) -> Result<(), JsonError> {
+        if let Some(max) = self.tokenizer.options().max_depth
+            && self.containers.len() >= max
+        {
+            return Err(JsonError::DepthLimitExceeded(max, start));
+        }
+        self.containers.push(container);
+        Ok(())
+    }
+
+    fn expect_colon(&mut self) -> Result<(), JsonError> {
+        match self.tokenizer.next().transpose()? {
+            Some(SpannedToken {
+                token: Token::Colon,
+                ..
+            }) => Ok(()),
+            Some(SpannedToken { token, span }) => Err(JsonError::CollonError(token, span.start)),
+            None => Err(JsonError::UnexpectedEndOfJson(self.tokenizer.position())),
+        }
+    }
+
+    /// Leaves the innermost container, returning its end event
+    fn close(&mut self) -> Event {
+        let event = match self.containers.pop() {
+            Some(Container::Object { .. }) => Event::EndObject,
+            _ => Event::EndArray,
+        };
+        self.end_value();
+        event
+    }
+
+    /// Moves on to what follows a complete value in the current container
+    fn end_value(&mut self) {
+        self.expect = match self.containers.last() {
+            Some(Container::Object { .. }) => Expect::CommaOrEndObject,
+            Some(Container::Array) => Expect::CommaOrEndArray,
+            None => Expect::Done,
+        };
+    }
+}
+
+impl<S: Source> Iterator for PullParser<S> {
+    type Item = Result<Event, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.expect == Expect::Finished {
+            return None;
+        }
+        let event = self.next_event().transpose();
+        if !matches!(event, Some(Ok(_))) {
+            self.expect = Expect::Finished;
+        }
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::from_string;
+    use std::io;
+
+    fn events(json: &str) -> Result<Vec<Event>, JsonError> {
+        from_reader(json.as_bytes()).collect()
+    }
+
+    /// Hands out one byte per read
+    struct ByteReader<'a>(&'a [u8]);
+
+    impl Read for ByteReader<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buffer[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk unplugged"))
+        }
+    }
+
+    #[test]
+    fn it_should_emit_events_in_document_order() {
+        let json = r#"{"name": "Alice", "tags": ["a", []], "address": {}, "age": 30}"#;
+
+        assert_eq!(
+            vec![
+                Event::StartObject,
+                Event::Key("name".to_string()),
+                Event::Value(JsonType::Str("Alice".to_string())),
+                Event::Key("tags".to_string()),
+                Event::StartArray,
+                Event::Value(JsonType::Str("a".to_string())),
+                Event::StartArray,
+                Event::EndArray,
+                Event::EndArray,
+                Event::Key("address".to_string()),
+                Event::StartObject,
+                Event::EndObject,
+                Event::Key("age".to_string()),
+                Event::Value(JsonType::Num(Num::Integer(30))),
+                Event::EndObject,
+            ],
+            events(json).unwrap()
+        );
+        assert_eq!(
+            vec![Event::Value(JsonType::Null)],
+            events(" null ").unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_read_multibyte_characters_split_across_reads() {
+        let json = r#"["héllo", {"😀": "€"}]"#;
+        let parser = PullParser::new(Tokenizer::from_reader(ByteReader(json.as_bytes())));
+
+        assert_eq!(
+            events(json).unwrap(),
+            parser.collect::<Result<Vec<_>, _>>().unwrap()
+        );
+        assert_eq!(
+            Some(Event::Key("😀".to_string())),
+            events(json).unwrap().into_iter().nth(3)
+        );
+    }
+
+    #[test]
+    fn it_should_return_the_errors_of_the_parser() {
+        for json in [
+            "",
+            "[1, 2",
+            "[1 2]",
+            "[1,]",
+            r#"{"a" 1}"#,
+            r#"{"a": 1,}"#,
+            r#"{1: 2}"#,
+            "[1] 2",
+            "[@]",
+        ] {
+            assert_eq!(
+                from_string::<JsonType>(json).unwrap_err(),
+                events(json).unwrap_err(),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_stop_after_an_error() {
+        let mut parser = from_reader("[1 2, 3]".as_bytes());
+
+        assert_eq!(Some(Ok(Event::StartArray)), parser.next());
+        assert!(parser.next().unwrap().is_ok());
+        assert!(parser.next().unwrap().is_err());
+        assert_eq!(None, parser.next());
+    }
+
+    #[test]
+    fn it_should_report_invalid_utf8_and_read_errors() {
+        let mut parser = from_reader(&b"[\"a\xff\"]"[..]);
+
+        assert_eq!(Some(Ok(Event::StartArray)), parser.next());
+        assert_eq!(
+            Some(Err(JsonError::InvalidUtf8(Position::new(3, 1, 4)))),
+            parser.next()
+        );
+
+        let mut parser = from_reader(FailingReader);
+        assert_eq!(
+            Some(Err(JsonError::IoError("disk unplugged".to_string()))),
+            parser.next()
+        );
+    }
+
+    #[test]
+    fn it_should_track_the_depth() {
+        let mut parser = from_reader(r#"{"a": [[1]]}"#.as_bytes());
+        let mut depths = Vec::new();
+        while let Some(event) = parser.next() {
+            event.unwrap();
+            depths.push(parser.depth());
+        }
+
+        assert_eq!(vec![1, 1, 2, 3, 3, 2, 1, 0], depths);
+    }
+
+    #[test]
+    fn it_should_apply_the_limits() {
+        let options = ParserOptions {
+            max_depth: Some(2),
+            max_object_members: Some(1),
+            ..ParserOptions::default()
+        };
+        let events = |json: &str| {
+            from_reader_with_options(json.as_bytes(), options.clone())
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        assert!(events(r#"[{"a": 1}, {"b": 2}]"#).is_ok());
+        assert_eq!(
+            Err(JsonError::DepthLimitExceeded(2, Position::new(2, 1, 3))),
+            events("[[[]]]")
+        );
+        assert_eq!(
+            Err(JsonError::TooManyMembers(1, Position::new(9, 1, 10))),
+            events(r#"{"a": 1, "b": 2}"#)
+        );
+    }
+
+    #[test]
+    fn it_should_pull_json5_events() {
+        let options = ParserOptions {
+            json5: true,
+            ..ParserOptions::default()
+        };
+        let events: Result<Vec<Event>, _> =
+            from_reader_with_options("{a: [1,], // comment\n}".as_bytes(), options).collect();
+
+        assert_eq!(
+            Ok(vec![
+                Event::StartObject,
+                Event::Key("a".to_string()),
+                Event::StartArray,
+                Event::Value(JsonType::Num(Num::Integer(1))),
+                Event::EndArray,
+                Event::EndObject,
+            ]),
+            events
+        );
+    }
+}
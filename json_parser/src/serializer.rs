@@ -1,79 +1,283 @@
-use crate::JsonType;
+//! Streaming serialization
+//!
+//! [`Serialize::serialize_into`] drives a [`Serializer`] value by value, so
+//! the output is written as it is produced, without building a [`JsonType`]
+//! or intermediate strings. [`WriterSerializer`] writes compact JSON to any
+//! [`std::io::Write`] and [`PrettyFormatter`] spreads it over indented lines.
+//! [`to_value`] builds the [`JsonType`] instead, reporting errors that
+//! [`Serialize::serialize`] can only panic on.
+
+use std::io::Write;
+
 use crate::error::JsonError;
-use crate::types::Object;
+use crate::options::RECURSION_LIMIT;
+use crate::types::Num;
+use crate::{JsonType, Serialize};
 
-pub fn serialize_json(data: &JsonType) -> Result<String, JsonError> {
-    let mut retval = String::new();
-    match data {
-        JsonType::Str(value) => {
-            retval.push('"');
-            retval.push_str(value);
-            retval.push('"');
+mod pretty;
+mod value;
+
+pub use pretty::{Indent, Newline, PrettyFormatter, PrettySerializer};
+pub use value::{ValueMap, ValueSeq, ValueSerializer, to_value};
+
+/// Receives a value piece by piece
+pub trait Serializer {
+    type Seq<'a>: SerializeSeq
+    where
+        Self: 'a;
+    type Map<'a>: SerializeMap
+    where
+        Self: 'a;
+
+    fn serialize_null(&mut self) -> Result<(), JsonError>;
+    fn serialize_bool(&mut self, value: bool) -> Result<(), JsonError>;
+    fn serialize_i64(&mut self, value: i64) -> Result<(), JsonError>;
+    fn serialize_u64(&mut self, value: u64) -> Result<(), JsonError>;
+    fn serialize_f64(&mut self, value: f64) -> Result<(), JsonError>;
+    /// A number already written out, as kept by [`Num::Decimal`]
+    fn serialize_decimal(&mut self, value: &str) -> Result<(), JsonError>;
+    fn serialize_str(&mut self, value: &str) -> Result<(), JsonError>;
+    /// Starts an array, its elements are written through the returned value
+    fn serialize_seq(&mut self) -> Result<Self::Seq<'_>, JsonError>;
+    /// Starts an object, its members are written through the returned value
+    fn serialize_map(&mut self) -> Result<Self::Map<'_>, JsonError>;
+}
+
+/// Writes the elements of an array started by [`Serializer::serialize_seq`]
+pub trait SerializeSeq {
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError>;
+    fn end(self) -> Result<(), JsonError>;
+}
+
+/// Writes the members of an object started by [`Serializer::serialize_map`]
+pub trait SerializeMap {
+    type Serializer: Serializer + ?Sized;
+
+    /// Writes a key and returns the serializer its value must be written to,
+    /// exactly once
+    fn serialize_key(&mut self, key: &str) -> Result<&mut Self::Serializer, JsonError>;
+
+    fn serialize_entry<T: Serialize + ?Sized>(
+        &mut self,
+        key: &str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        value.serialize_into(self.serialize_key(key)?)
+    }
+
+    fn end(self) -> Result<(), JsonError>;
+}
+
+/// Writes a number to `serializer`
+pub(crate) fn serialize_num<S: Serializer + ?Sized>(
+    num: &Num,
+    serializer: &mut S,
+) -> Result<(), JsonError> {
+    match num {
+        Num::Integer(value) => serializer.serialize_i64(*value),
+        Num::UInteger(value) => serializer.serialize_u64(*value),
+        Num::Float(value) => serializer.serialize_f64(*value),
+        Num::Decimal(value) => serializer.serialize_decimal(value),
+    }
+}
+
+/// Fails when `depth` arrays and objects are open already and another one
+/// would exceed [`RECURSION_LIMIT`]
+pub(crate) fn check_depth(depth: usize) -> Result<(), JsonError> {
+    if depth >= RECURSION_LIMIT {
+        return Err(JsonError::SerializationError(format!(
+            "cannot serialize more than {} nested arrays and objects",
+            RECURSION_LIMIT
+        )));
+    }
+    Ok(())
+}
+
+/// A [`Serializer`] writing compact JSON to a [`Write`]
+///
+/// Arrays and objects nested deeper than [`RECURSION_LIMIT`] are an error.
+pub struct WriterSerializer<W> {
+    writer: W,
+    /// Number of arrays and objects being written
+    depth: usize,
+}
+
+impl<W: Write> WriterSerializer<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, depth: 0 }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Serializer for WriterSerializer<W> {
+    type Seq<'a>
+        = Compound<'a, W>
+    where
+        Self: 'a;
+    type Map<'a>
+        = Compound<'a, W>
+    where
+        Self: 'a;
+
+    fn serialize_null(&mut self) -> Result<(), JsonError> {
+        Ok(self.writer.write_all(b"null")?)
+    }
+
+    fn serialize_bool(&mut self, value: bool) -> Result<(), JsonError> {
+        let value: &[u8] = if value { b"true" } else { b"false" };
+        Ok(self.writer.write_all(value)?)
+    }
+
+    fn serialize_i64(&mut self, value: i64) -> Result<(), JsonError> {
+        Ok(write!(self.writer, "{}", value)?)
+    }
+
+    fn serialize_u64(&mut self, value: u64) -> Result<(), JsonError> {
+        Ok(write!(self.writer, "{}", value)?)
+    }
+
+    fn serialize_f64(&mut self, value: f64) -> Result<(), JsonError> {
+        if !value.is_finite() {
+            return Err(JsonError::SerializationError(format!(
+                "{} cannot be represented in JSON",
+                value
+            )));
         }
-        JsonType::Num(value) => {
-            retval.push_str(&value.serialize());
+        Ok(write!(self.writer, "{}", value)?)
+    }
+
+    fn serialize_decimal(&mut self, value: &str) -> Result<(), JsonError> {
+        Ok(self.writer.write_all(value.as_bytes())?)
+    }
+
+    fn serialize_str(&mut self, value: &str) -> Result<(), JsonError> {
+        Ok(escape_string(value, &mut self.writer)?)
+    }
+
+    fn serialize_seq(&mut self) -> Result<Self::Seq<'_>, JsonError> {
+        check_depth(self.depth)?;
+        self.depth += 1;
+        self.writer.write_all(b"[")?;
+        Ok(Compound::new(self))
+    }
+
+    fn serialize_map(&mut self) -> Result<Self::Map<'_>, JsonError> {
+        check_depth(self.depth)?;
+        self.depth += 1;
+        self.writer.write_all(b"{")?;
+        Ok(Compound::new(self))
+    }
+}
+
+/// An array or object being written by a [`WriterSerializer`]
+pub struct Compound<'a, W> {
+    serializer: &'a mut WriterSerializer<W>,
+    first: bool,
+}
+
+impl<'a, W: Write> Compound<'a, W> {
+    fn new(serializer: &'a mut WriterSerializer<W>) -> Self {
+        Self {
+            serializer,
+            first: true,
         }
-        JsonType::Bool(value) => {
-            if value == &true {
-                retval.push_str("true");
-            } else {
-                retval.push_str("false");
-            }
+    }
+
+    fn separate(&mut self) -> Result<(), JsonError> {
+        if !self.first {
+            self.serializer.writer.write_all(b",")?;
         }
-        JsonType::Array(value) => retval.push_str(&serialize_array(value)?),
-        JsonType::Object(value) => retval.push_str(&serialize_object(value)?),
+        self.first = false;
+        Ok(())
     }
-    Ok(retval)
 }
 
-fn serialize_array(input: &Vec<JsonType>) -> Result<String, JsonError> {
-    let mut retval = String::new();
-    let mut peek = input.iter().peekable();
-    retval.push('[');
-    loop {
-        let Some(next_input) = peek.next() else {
-            return Err(JsonError::SerializationError(
-                "Wrong array format".to_string(),
-            ));
-        };
-        retval.push_str(&serialize_json(next_input)?);
-        if let None = peek.peek() {
-            break;
-        }
-        retval.push(',');
+impl<W: Write> SerializeSeq for Compound<'_, W> {
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.separate()?;
+        value.serialize_into(&mut *self.serializer)
+    }
+
+    fn end(self) -> Result<(), JsonError> {
+        self.serializer.depth -= 1;
+        Ok(self.serializer.writer.write_all(b"]")?)
+    }
+}
+
+impl<W: Write> SerializeMap for Compound<'_, W> {
+    type Serializer = WriterSerializer<W>;
+
+    fn serialize_key(&mut self, key: &str) -> Result<&mut Self::Serializer, JsonError> {
+        self.separate()?;
+        escape_string(key, &mut self.serializer.writer)?;
+        self.serializer.writer.write_all(b":")?;
+        Ok(&mut *self.serializer)
     }
-    retval.push(']');
-    Ok(retval)
+
+    fn end(self) -> Result<(), JsonError> {
+        self.serializer.depth -= 1;
+        Ok(self.serializer.writer.write_all(b"}")?)
+    }
+}
+
+/// Writes `value` to `writer`
+pub fn to_writer<W: Write, T: Serialize + ?Sized>(writer: W, value: &T) -> Result<(), JsonError> {
+    value.serialize_into(&mut WriterSerializer::new(writer))
+}
+
+/// Writes `value` to a new byte vector
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, JsonError> {
+    let mut output = Vec::new();
+    to_writer(&mut output, value)?;
+    Ok(output)
+}
+
+pub fn serialize_json(data: &JsonType) -> Result<String, JsonError> {
+    let output = to_vec(data)?;
+    String::from_utf8(output).map_err(|error| JsonError::SerializationError(error.to_string()))
 }
 
-fn serialize_object(input: &Object) -> Result<String, JsonError> {
-    let mut retval = String::new();
-    let mut peek = input.data.iter().peekable();
-    retval.push('{');
-    loop {
-        let Some((key, value)) = peek.next() else {
-            return Err(JsonError::SerializationError(
-                "Wrong object format".to_string(),
-            ));
+/// Writes `value` as a quoted JSON string, escaping quotes, backslashes and
+/// control characters
+///
+/// Runs of characters which need no escaping are written in one go.
+fn escape_string<W: Write + ?Sized>(value: &str, writer: &mut W) -> std::io::Result<()> {
+    let bytes = value.as_bytes();
+    writer.write_all(b"\"")?;
+    let mut start = 0;
+    for (index, c) in value.char_indices() {
+        let escaped = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\u{8}' => "\\b",
+            '\u{c}' => "\\f",
+            c if c < '\u{20}' => {
+                writer.write_all(&bytes[start..index])?;
+                write!(writer, "\\u{:04x}", c as u32)?;
+                start = index + 1;
+                continue;
+            }
+            _ => continue,
         };
-        retval.push('"');
-        retval.push_str(&key);
-        retval.push('"');
-        retval.push_str(":");
-        retval.push_str(&serialize_json(value)?);
-        if let None = peek.peek() {
-            break;
-        }
-        retval.push(',');
+        writer.write_all(&bytes[start..index])?;
+        writer.write_all(escaped.as_bytes())?;
+        start = index + 1;
     }
-    retval.push('}');
-    Ok(retval)
+    writer.write_all(&bytes[start..])?;
+    writer.write_all(b"\"")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::types::{Num, Object};
+    use crate::{ParserOptions, from_string_with_options};
 
     #[test]
     fn it_should_serialize_string() {
@@ -83,6 +287,14 @@ mod tests {
         assert_eq!("\"Hello, World\"", result);
     }
 
+    #[test]
+    fn it_should_escape_string() {
+        let input = JsonType::Str("say \"hi\"\\\n\t\u{1}é".to_string());
+        let result = serialize_json(&input).unwrap();
+
+        assert_eq!(r#""say \"hi\"\\\n\t\u0001é""#, result);
+    }
+
     #[test]
     fn it_should_serialize_num() {
         let input = JsonType::Num(Num::Integer(54));
@@ -91,6 +303,17 @@ mod tests {
         assert_eq!("54", result);
     }
 
+    #[test]
+    fn it_should_serialize_big_numbers() {
+        let input = JsonType::Array(vec![
+            JsonType::Num(Num::UInteger(u64::MAX)),
+            JsonType::Num(Num::Decimal("-1e400".to_string())),
+        ]);
+        let result = serialize_json(&input).unwrap();
+
+        assert_eq!("[18446744073709551615,-1e400]", result);
+    }
+
     #[test]
     fn it_should_serialize_bool() {
         let input = JsonType::Bool(true);
@@ -99,6 +322,14 @@ mod tests {
         assert_eq!("true", result);
     }
 
+    #[test]
+    fn it_should_serialize_null() {
+        let input = JsonType::Null;
+        let result = serialize_json(&input).unwrap();
+
+        assert_eq!("null", result);
+    }
+
     #[test]
     fn it_should_serialize_array() {
         let v = vec![JsonType::Str("hello".to_string()), JsonType::Bool(true)];
@@ -110,15 +341,110 @@ mod tests {
 
     #[test]
     fn it_should_serialize_object() {
-        let v = Object {
-            data: vec![
-                ("key1".to_string(), JsonType::Str("hello".to_string())),
-                ("key2".to_string(), JsonType::Bool(true)),
-            ],
-        };
+        let v = Object::new(vec![
+            ("key1".to_string(), JsonType::Str("hello".to_string())),
+            ("key2".to_string(), JsonType::Bool(true)),
+        ]);
         let input = JsonType::Object(Box::new(v));
         let result = serialize_json(&input).unwrap();
 
         assert_eq!("{\"key1\":\"hello\",\"key2\":true}", result);
     }
+
+    #[test]
+    fn it_should_serialize_empty_containers() {
+        let input = JsonType::Array(vec![
+            JsonType::Array(vec![]),
+            JsonType::Object(Box::new(Object::new(vec![]))),
+        ]);
+        let result = serialize_json(&input).unwrap();
+
+        assert_eq!("[[],{}]", result);
+    }
+
+    #[test]
+    fn it_should_stream_to_writer() {
+        let input = JsonType::Array(vec![
+            JsonType::Str("a\"b".to_string()),
+            JsonType::Num(Num::Float(1.5)),
+        ]);
+        let mut output = Vec::new();
+        to_writer(&mut output, &input).unwrap();
+
+        assert_eq!(br#"["a\"b",1.5]"#.to_vec(), output);
+        assert_eq!(output, to_vec(&input).unwrap());
+    }
+
+    #[test]
+    fn it_should_stream_std_types_without_json_type() {
+        let input = (
+            vec![1u8, 2],
+            Some("x"),
+            std::collections::BTreeMap::from([("k", true)]),
+        );
+
+        assert_eq!(
+            br#"[[1,2],"x",{"k":true}]"#.to_vec(),
+            to_vec(&input).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_reject_non_finite_floats() {
+        let result = to_vec(&f64::NAN);
+
+        assert_eq!(
+            Err(JsonError::SerializationError(
+                "NaN cannot be represented in JSON".to_string()
+            )),
+            result
+        );
+    }
+
+    #[test]
+    fn it_should_report_write_errors() {
+        struct Full;
+
+        impl Write for Full {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk full"))
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let result = to_writer(Full, &JsonType::Null);
+
+        assert_eq!(Err(JsonError::IoError("disk full".to_string())), result);
+    }
+
+    #[test]
+    fn it_should_stop_at_the_recursion_limit() {
+        let options = ParserOptions {
+            max_depth: None,
+            ..ParserOptions::default()
+        };
+        let nested = |depth| {
+            let json = format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+            from_string_with_options::<JsonType>(&json, options.clone()).unwrap()
+        };
+        let error = || {
+            JsonError::SerializationError(
+                "cannot serialize more than 128 nested arrays and objects".to_string(),
+            )
+        };
+
+        let value = nested(RECURSION_LIMIT);
+        assert!(serialize_json(&value).is_ok());
+        assert!(PrettyFormatter::default().to_string(&value).is_ok());
+        assert_eq!(Ok(value.clone()), to_value(&value));
+
+        let value = nested(200_000);
+        assert_eq!(Err(error()), serialize_json(&value));
+        assert_eq!(Err(error()), PrettyFormatter::default().to_string(&value));
+        assert_eq!(Err(error()), to_value(&value));
+        value.drop_iteratively();
+    }
 }
@@ -0,0 +1,531 @@
+use std::io::Write;
+
+use super::{SerializeMap, SerializeSeq, Serializer, WriterSerializer, check_depth, escape_string};
+use crate::Serialize;
+use crate::error::JsonError;
+
+/// Indentation of each nesting level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    Spaces(usize),
+    Tabs,
+}
+
+impl Default for Indent {
+    fn default() -> Self {
+        Indent::Spaces(2)
+    }
+}
+
+/// Line ending written between lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Newline {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+/// Writes JSON spread over several indented lines
+///
+/// The value is streamed through a [`PrettySerializer`].
+///
+/// # Examples
+///
+/// ```
+/// use json_parser::serializer::{Indent, PrettyFormatter};
+/// use json_parser::{from_string, JsonType};
+///
+/// let json: JsonType = from_string(r#"{"b": [1, 2], "a": {"c": null}}"#).unwrap();
+/// let formatter = PrettyFormatter {
+///     indent: Indent::Spaces(4),
+///     sort_keys: true,
+///     inline_arrays: Some(20),
+///     ..PrettyFormatter::default()
+/// };
+///
+/// assert_eq!(
+///     "{\n    \"a\": {\n        \"c\": null\n    },\n    \"b\": [1, 2]\n}",
+///     formatter.to_string(&json).unwrap()
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrettyFormatter {
+    pub indent: Indent,
+    pub newline: Newline,
+    /// Writes `"key": value` instead of `"key":value`
+    pub space_after_colon: bool,
+    /// Writes the members of objects sorted by key instead of in insertion order
+    pub sort_keys: bool,
+    /// Writes arrays of scalars on a single line when it is at most this many
+    /// characters long
+    pub inline_arrays: Option<usize>,
+}
+
+impl Default for PrettyFormatter {
+    fn default() -> Self {
+        Self {
+            indent: Indent::default(),
+            newline: Newline::default(),
+            space_after_colon: true,
+            sort_keys: false,
+            inline_arrays: None,
+        }
+    }
+}
+
+impl PrettyFormatter {
+    /// Writes `value` to `writer`
+    pub fn to_writer<W: Write, T: Serialize + ?Sized>(
+        &self,
+        writer: W,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        value.serialize_into(&mut PrettySerializer::new(writer, self))
+    }
+
+    /// Writes `value` to a new string
+    pub fn to_string<T: Serialize + ?Sized>(&self, value: &T) -> Result<String, JsonError> {
+        let mut output = Vec::new();
+        self.to_writer(&mut output, value)?;
+        String::from_utf8(output).map_err(|error| JsonError::SerializationError(error.to_string()))
+    }
+}
+
+/// A [`Serializer`] writing JSON laid out by a [`PrettyFormatter`]
+///
+/// Values are written as they come. Only the members of an object whose keys
+/// are sorted are held back until it ends, along with the start of an array
+/// which may still fit on one line. Arrays and objects nested deeper than
+/// [`RECURSION_LIMIT`](crate::RECURSION_LIMIT) are an error.
+pub struct PrettySerializer<'f, W> {
+    writer: W,
+    formatter: &'f PrettyFormatter,
+    /// Number of arrays and objects being written
+    depth: usize,
+    /// The members of the objects being sorted, the innermost last
+    sorted: Vec<Vec<u8>>,
+    inline: Inline,
+}
+
+/// The array being written which may still fit on one line
+///
+/// Only the innermost array can, as one holding an array or an object is
+/// spread over several lines.
+#[derive(Default)]
+struct Inline {
+    active: bool,
+    /// The elements written so far, one after the other
+    text: Vec<u8>,
+    /// Where each element ends in `text`
+    ends: Vec<usize>,
+    /// Length of the line the elements would take, brackets included
+    chars: usize,
+}
+
+impl Inline {
+    /// Ends the array, keeping the buffers for the next one
+    fn clear(&mut self) {
+        self.active = false;
+        self.text.clear();
+        self.ends.clear();
+        self.chars = 0;
+    }
+}
+
+impl<'f, W: Write> PrettySerializer<'f, W> {
+    pub fn new(writer: W, formatter: &'f PrettyFormatter) -> Self {
+        Self {
+            writer,
+            formatter,
+            depth: 0,
+            sorted: Vec::new(),
+            inline: Inline::default(),
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Where the output currently goes, the members of a sorted object being
+    /// held back
+    fn output(&mut self) -> &mut dyn Write {
+        match self.sorted.last_mut() {
+            Some(members) => members,
+            None => &mut self.writer,
+        }
+    }
+
+    /// Writes a scalar, as an element of the array on one line if there is one
+    fn scalar(
+        &mut self,
+        write: impl FnOnce(&mut WriterSerializer<&mut dyn Write>) -> Result<(), JsonError>,
+    ) -> Result<(), JsonError> {
+        if !self.inline.active {
+            return write(&mut WriterSerializer::new(self.output()));
+        }
+        let start = self.inline.text.len();
+        write(&mut WriterSerializer::new(&mut self.inline.text))?;
+        self.inline.ends.push(self.inline.text.len());
+        let element = String::from_utf8_lossy(&self.inline.text[start..])
+            .chars()
+            .count();
+        let separator = if self.inline.ends.len() > 1 { 2 } else { 0 };
+        self.inline.chars += separator + element;
+        match self.formatter.inline_arrays {
+            Some(width) if self.inline.chars <= width => Ok(()),
+            _ => self.spread_inline(),
+        }
+    }
+
+    /// Spreads the array held on one line over several lines, as it got too
+    /// long or holds a container
+    fn spread_inline(&mut self) -> Result<(), JsonError> {
+        if !self.inline.active {
+            return Ok(());
+        }
+        let mut inline = std::mem::take(&mut self.inline);
+        self.output().write_all(b"[")?;
+        let mut start = 0;
+        for (index, &end) in inline.ends.iter().enumerate() {
+            self.start_line(index)?;
+            self.output().write_all(&inline.text[start..end])?;
+            start = end;
+        }
+        inline.clear();
+        self.inline = inline;
+        Ok(())
+    }
+
+    /// Goes one level deeper for an array or an object, spreading the array
+    /// holding it over several lines first
+    fn open(&mut self) -> Result<(), JsonError> {
+        check_depth(self.depth)?;
+        if self.inline.active {
+            let index = self.inline.ends.len();
+            self.spread_inline()?;
+            self.start_line(index)?;
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Ends the previous element, if any, and indents the next one
+    fn start_line(&mut self, index: usize) -> Result<(), JsonError> {
+        if index > 0 {
+            self.output().write_all(b",")?;
+        }
+        self.write_newline()?;
+        self.write_indent(self.depth)
+    }
+
+    /// Closes an array or an object of `len` items with `bracket`
+    fn close(&mut self, len: usize, bracket: &[u8]) -> Result<(), JsonError> {
+        self.depth -= 1;
+        if len > 0 {
+            self.write_newline()?;
+            self.write_indent(self.depth)?;
+        }
+        Ok(self.output().write_all(bracket)?)
+    }
+
+    fn write_newline(&mut self) -> Result<(), JsonError> {
+        let newline: &[u8] = match self.formatter.newline {
+            Newline::Lf => b"\n",
+            Newline::CrLf => b"\r\n",
+        };
+        Ok(self.output().write_all(newline)?)
+    }
+
+    fn write_indent(&mut self, depth: usize) -> Result<(), JsonError> {
+        let indent = self.formatter.indent;
+        let output = self.output();
+        for _ in 0..depth {
+            match indent {
+                Indent::Spaces(width) => write!(output, "{:width$}", "", width = width)?,
+                Indent::Tabs => output.write_all(b"\t")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'f, W: Write> Serializer for PrettySerializer<'f, W> {
+    type Seq<'a>
+        = PrettySeq<'a, 'f, W>
+    where
+        Self: 'a;
+    type Map<'a>
+        = PrettyMap<'a, 'f, W>
+    where
+        Self: 'a;
+
+    fn serialize_null(&mut self) -> Result<(), JsonError> {
+        self.scalar(|serializer| serializer.serialize_null())
+    }
+
+    fn serialize_bool(&mut self, value: bool) -> Result<(), JsonError> {
+        self.scalar(|serializer| serializer.serialize_bool(value))
+    }
+
+    fn serialize_i64(&mut self, value: i64) -> Result<(), JsonError> {
+        self.scalar(|serializer| serializer.serialize_i64(value))
+    }
+
+    fn serialize_u64(&mut self, value: u64) -> Result<(), JsonError> {
+        self.scalar(|serializer| serializer.serialize_u64(value))
+    }
+
+    fn serialize_f64(&mut self, value: f64) -> Result<(), JsonError> {
+        self.scalar(|serializer| serializer.serialize_f64(value))
+    }
+
+    fn serialize_decimal(&mut self, value: &str) -> Result<(), JsonError> {
+        self.scalar(|serializer| serializer.serialize_decimal(value))
+    }
+
+    fn serialize_str(&mut self, value: &str) -> Result<(), JsonError> {
+        self.scalar(|serializer| serializer.serialize_str(value))
+    }
+
+    fn serialize_seq(&mut self) -> Result<Self::Seq<'_>, JsonError> {
+        self.open()?;
+        if self.formatter.inline_arrays.is_some() {
+            self.inline.active = true;
+            self.inline.chars = 2;
+        } else {
+            self.output().write_all(b"[")?;
+        }
+        Ok(PrettySeq {
+            serializer: self,
+            len: 0,
+        })
+    }
+
+    fn serialize_map(&mut self) -> Result<Self::Map<'_>, JsonError> {
+        self.open()?;
+        self.output().write_all(b"{")?;
+        if self.formatter.sort_keys {
+            self.sorted.push(Vec::new());
+        }
+        Ok(PrettyMap {
+            serializer: self,
+            len: 0,
+            starts: Vec::new(),
+        })
+    }
+}
+
+/// An array being written by a [`PrettySerializer`]
+pub struct PrettySeq<'a, 'f, W> {
+    serializer: &'a mut PrettySerializer<'f, W>,
+    len: usize,
+}
+
+impl<W: Write> SerializeSeq for PrettySeq<'_, '_, W> {
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        if !self.serializer.inline.active {
+            self.serializer.start_line(self.len)?;
+        }
+        self.len += 1;
+        value.serialize_into(&mut *self.serializer)
+    }
+
+    fn end(self) -> Result<(), JsonError> {
+        let serializer = self.serializer;
+        if !serializer.inline.active {
+            return serializer.close(self.len, b"]");
+        }
+        // Every element was a scalar and the line is short enough
+        let mut inline = std::mem::take(&mut serializer.inline);
+        serializer.depth -= 1;
+        let output = serializer.output();
+        output.write_all(b"[")?;
+        let mut start = 0;
+        for (index, &end) in inline.ends.iter().enumerate() {
+            if index > 0 {
+                output.write_all(b", ")?;
+            }
+            output.write_all(&inline.text[start..end])?;
+            start = end;
+        }
+        output.write_all(b"]")?;
+        inline.clear();
+        serializer.inline = inline;
+        Ok(())
+    }
+}
+
+/// An object being written by a [`PrettySerializer`]
+pub struct PrettyMap<'a, 'f, W> {
+    serializer: &'a mut PrettySerializer<'f, W>,
+    len: usize,
+    /// The keys of a sorted object and where their member starts
+    starts: Vec<(String, usize)>,
+}
+
+impl<'f, W: Write> SerializeMap for PrettyMap<'_, 'f, W> {
+    type Serializer = PrettySerializer<'f, W>;
+
+    fn serialize_key(&mut self, key: &str) -> Result<&mut Self::Serializer, JsonError> {
+        let serializer = &mut *self.serializer;
+        if serializer.formatter.sort_keys {
+            let start = serializer.sorted.last().map_or(0, Vec::len);
+            self.starts.push((key.to_string(), start));
+        } else {
+            serializer.start_line(self.len)?;
+        }
+        self.len += 1;
+        let colon: &[u8] = if serializer.formatter.space_after_colon {
+            b": "
+        } else {
+            b":"
+        };
+        let output = serializer.output();
+        escape_string(key, output)?;
+        output.write_all(colon)?;
+        Ok(serializer)
+    }
+
+    fn end(self) -> Result<(), JsonError> {
+        let serializer = self.serializer;
+        if serializer.formatter.sort_keys {
+            let members = serializer.sorted.pop().unwrap_or_default();
+            let mut ranges: Vec<_> = self
+                .starts
+                .iter()
+                .enumerate()
+                .map(|(index, (key, start))| {
+                    let end = self
+                        .starts
+                        .get(index + 1)
+                        .map_or(members.len(), |next| next.1);
+                    (key.as_str(), *start..end)
+                })
+                .collect();
+            ranges.sort_by_key(|(key, _)| *key);
+            for (index, (_, range)) in ranges.into_iter().enumerate() {
+                serializer.start_line(index)?;
+                serializer.output().write_all(&members[range])?;
+            }
+        }
+        serializer.close(self.len, b"}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{JsonType, from_string};
+
+    fn parse(json: &str) -> JsonType {
+        from_string(json).unwrap()
+    }
+
+    #[test]
+    fn it_should_indent_nested_containers() {
+        let json = parse(r#"{"name": "Alice", "tags": ["a", "b"], "address": {"city": "Paris"}}"#);
+
+        assert_eq!(
+            r#"{
+  "name": "Alice",
+  "tags": [
+    "a",
+    "b"
+  ],
+  "address": {
+    "city": "Paris"
+  }
+}"#,
+            PrettyFormatter::default().to_string(&json).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_keep_empty_containers_and_scalars_on_one_line() {
+        let formatter = PrettyFormatter::default();
+
+        assert_eq!("[]", formatter.to_string(&parse("[]")).unwrap());
+        assert_eq!("{}", formatter.to_string(&parse("{}")).unwrap());
+        assert_eq!("\"a\\nb\"", formatter.to_string("a\nb").unwrap());
+        assert_eq!(
+            "[\n  [],\n  {}\n]",
+            formatter.to_string(&parse("[[], {}]")).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_use_tabs_crlf_and_no_space_after_colon() {
+        let formatter = PrettyFormatter {
+            indent: Indent::Tabs,
+            newline: Newline::CrLf,
+            space_after_colon: false,
+            ..PrettyFormatter::default()
+        };
+
+        assert_eq!(
+            "{\r\n\t\"a\":{\r\n\t\t\"b\":1\r\n\t}\r\n}",
+            formatter.to_string(&parse(r#"{"a": {"b": 1}}"#)).unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_sort_keys() {
+        let formatter = PrettyFormatter {
+            sort_keys: true,
+            ..PrettyFormatter::default()
+        };
+
+        assert_eq!(
+            "{\n  \"a\": 2,\n  \"b\": {\n    \"c\": 3,\n    \"d\": 4\n  }\n}",
+            formatter
+                .to_string(&parse(r#"{"b": {"d": 4, "c": 3}, "a": 2}"#))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_inline_short_arrays_of_scalars() {
+        let formatter = PrettyFormatter {
+            inline_arrays: Some(12),
+            ..PrettyFormatter::default()
+        };
+
+        assert_eq!(
+            "[\n  [1, 2, 3],\n  [\n    1000000,\n    2000000\n  ],\n  [\n    [1]\n  ]\n]",
+            formatter
+                .to_string(&parse("[[1, 2, 3], [1000000, 2000000], [[1]]]"))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_spread_arrays_holding_containers_or_too_long() {
+        let formatter = PrettyFormatter {
+            inline_arrays: Some(10),
+            sort_keys: true,
+            ..PrettyFormatter::default()
+        };
+
+        assert_eq!(
+            "{\n  \"a\": [\n    1,\n    {\n      \"c\": [2]\n    }\n  ],\n  \"b\": [\n    \"long\",\n    \"line\"\n  ]\n}",
+            formatter
+                .to_string(&parse(r#"{"b": ["long", "line"], "a": [1, {"c": [2]}]}"#))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_stream_std_types() {
+        let value = std::collections::BTreeMap::from([("k", vec![Some(1u8), None])]);
+        let formatter = PrettyFormatter {
+            inline_arrays: Some(20),
+            ..PrettyFormatter::default()
+        };
+
+        assert_eq!(
+            "{\n  \"k\": [1, null]\n}",
+            formatter.to_string(&value).unwrap()
+        );
+    }
+}
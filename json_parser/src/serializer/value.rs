@@ -0,0 +1,155 @@
+use crate::error::JsonError;
+use crate::types::{Num, Object};
+use crate::{JsonType, Serialize};
+
+use super::{SerializeMap, SerializeSeq, Serializer, check_depth};
+
+/// A [`Serializer`] building a [`JsonType`]
+///
+/// Unlike [`Serialize::serialize`], building the tree through this serializer
+/// reports values that cannot be represented as errors, such as arrays and
+/// objects nested deeper than [`RECURSION_LIMIT`](crate::RECURSION_LIMIT).
+#[derive(Default)]
+pub struct ValueSerializer {
+    value: Option<JsonType>,
+    /// Number of arrays and objects holding the value
+    depth: usize,
+}
+
+impl ValueSerializer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A serializer for a value held by the array or object being built
+    fn nested(&self) -> Self {
+        Self {
+            value: None,
+            depth: self.depth + 1,
+        }
+    }
+
+    /// Returns the value written so far, `null` if nothing was written
+    pub fn into_inner(self) -> JsonType {
+        self.value.unwrap_or(JsonType::Null)
+    }
+
+    fn set(&mut self, value: JsonType) -> Result<(), JsonError> {
+        self.value = Some(value);
+        Ok(())
+    }
+}
+
+impl Serializer for ValueSerializer {
+    type Seq<'a> = ValueSeq<'a>;
+    type Map<'a> = ValueMap<'a>;
+
+    fn serialize_null(&mut self) -> Result<(), JsonError> {
+        self.set(JsonType::Null)
+    }
+
+    fn serialize_bool(&mut self, value: bool) -> Result<(), JsonError> {
+        self.set(JsonType::Bool(value))
+    }
+
+    fn serialize_i64(&mut self, value: i64) -> Result<(), JsonError> {
+        self.set(JsonType::Num(Num::Integer(value)))
+    }
+
+    fn serialize_u64(&mut self, value: u64) -> Result<(), JsonError> {
+        self.set(JsonType::Num(Num::UInteger(value)))
+    }
+
+    fn serialize_f64(&mut self, value: f64) -> Result<(), JsonError> {
+        self.set(JsonType::Num(Num::Float(value)))
+    }
+
+    fn serialize_decimal(&mut self, value: &str) -> Result<(), JsonError> {
+        self.set(JsonType::Num(Num::Decimal(value.to_string())))
+    }
+
+    fn serialize_str(&mut self, value: &str) -> Result<(), JsonError> {
+        self.set(JsonType::Str(value.to_string()))
+    }
+
+    fn serialize_seq(&mut self) -> Result<Self::Seq<'_>, JsonError> {
+        check_depth(self.depth)?;
+        Ok(ValueSeq {
+            serializer: self,
+            elements: Vec::new(),
+        })
+    }
+
+    fn serialize_map(&mut self) -> Result<Self::Map<'_>, JsonError> {
+        check_depth(self.depth)?;
+        Ok(ValueMap {
+            value: self.nested(),
+            serializer: self,
+            members: Vec::new(),
+            key: None,
+        })
+    }
+}
+
+/// An array being built by a [`ValueSerializer`]
+pub struct ValueSeq<'a> {
+    serializer: &'a mut ValueSerializer,
+    elements: Vec<JsonType>,
+}
+
+impl SerializeSeq for ValueSeq<'_> {
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        let mut serializer = self.serializer.nested();
+        value.serialize_into(&mut serializer)?;
+        self.elements.push(serializer.into_inner());
+        Ok(())
+    }
+
+    fn end(self) -> Result<(), JsonError> {
+        self.serializer.set(JsonType::Array(self.elements))
+    }
+}
+
+/// An object being built by a [`ValueSerializer`]
+///
+/// The value of a key is collected when the next key or the end is written.
+pub struct ValueMap<'a> {
+    serializer: &'a mut ValueSerializer,
+    members: Vec<(String, JsonType)>,
+    key: Option<String>,
+    value: ValueSerializer,
+}
+
+impl ValueMap<'_> {
+    fn push_pending(&mut self) {
+        if let Some(key) = self.key.take() {
+            let next = self.serializer.nested();
+            let value = std::mem::replace(&mut self.value, next).into_inner();
+            self.members.push((key, value));
+        }
+    }
+}
+
+impl SerializeMap for ValueMap<'_> {
+    type Serializer = ValueSerializer;
+
+    fn serialize_key(&mut self, key: &str) -> Result<&mut Self::Serializer, JsonError> {
+        self.push_pending();
+        self.key = Some(key.to_string());
+        Ok(&mut self.value)
+    }
+
+    fn end(mut self) -> Result<(), JsonError> {
+        self.push_pending();
+        let object = Object::new(self.members);
+        self.serializer.set(JsonType::Object(Box::new(object)))
+    }
+}
+
+/// Builds the [`JsonType`] of `value`, reporting values that cannot be
+/// represented rather than panicking
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<JsonType, JsonError> {
+    let mut serializer = ValueSerializer::new();
+    value.serialize_into(&mut serializer)?;
+    Ok(serializer.into_inner())
+}
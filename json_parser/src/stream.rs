@@ -0,0 +1,149 @@
+//! Back-to-back JSON values read from a single input
+//!
+//! # Examples
+//!
+//! ```
+//! use json_parser::stream::StreamDeserializer;
+//! use json_parser::JsonType;
+//! use json_parser::types::Num;
+//!
+//! let mut stream = StreamDeserializer::<JsonType>::new(r#"{"a": 1} [2]  "three""#);
+//!
+//! assert!(stream.next().unwrap().is_ok());
+//! assert_eq!(8, stream.byte_offset());
+//! assert_eq!(JsonType::Array(vec![JsonType::Num(Num::Integer(2))]), stream.next().unwrap().unwrap());
+//! assert_eq!(12, stream.byte_offset());
+//! ```
+
+use std::marker::PhantomData;
+
+use crate::Deserialize;
+use crate::error::JsonError;
+use crate::options::ParserOptions;
+use crate::parser::Parser;
+use crate::token::tokenizer::Tokenizer;
+
+/// Parses one value after another from concatenated input such as
+/// `{...}{...}[...]`
+///
+/// Values may be separated by any whitespace, or by nothing when the first
+/// one ends with a bracket or a quote. Parsing stops at the first error;
+/// [`byte_offset`](StreamDeserializer::byte_offset) tells how much of the
+/// input the complete values used, so reading can resume there once more
+/// input is available.
+#[derive(Debug)]
+pub struct StreamDeserializer<'a, T> {
+    parser: Parser<'a>,
+    offset: usize,
+    failed: bool,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: Deserialize> StreamDeserializer<'a, T> {
+    pub fn new(input: &'a str) -> Self {
+        Self::with_options(input, ParserOptions::default())
+    }
+
+    pub fn with_options(input: &'a str, options: ParserOptions) -> Self {
+        Self {
+            parser: Parser::new(Tokenizer::with_options(input, options)),
+            offset: 0,
+            failed: false,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the number of bytes of input used by the values read so far
+    ///
+    /// Whitespace after the last value is not included.
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<T: Deserialize> Iterator for StreamDeserializer<'_, T> {
+    type Item = Result<T, JsonError>;
+
+    /// Returns the next value, a value which parses but does not
+    /// deserialize into `T` does not end the stream
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.parser.parse_next() {
+            Ok(Some(value)) => {
+                self.offset = self.parser.position().offset;
+                Some(T::deserialize(value))
+            }
+            Ok(None) => None,
+            Err(error) => {
+                self.failed = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JsonType;
+    use crate::token::span::Position;
+
+    #[test]
+    fn it_should_read_concatenated_values() {
+        let input = "{\"a\":1}{\"b\":2}[3]\n\t \"four\" 5 null";
+        let values: Vec<JsonType> = StreamDeserializer::new(input)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(6, values.len());
+        assert_eq!(JsonType::Str("four".to_string()), values[3]);
+        assert_eq!(JsonType::Null, values[5]);
+    }
+
+    #[test]
+    fn it_should_report_the_offset_after_each_value() {
+        let input = "[1] {\"a\": [2, 3]}  ";
+        let mut stream = StreamDeserializer::<JsonType>::new(input);
+        let mut offsets = Vec::new();
+        while let Some(value) = stream.next() {
+            value.unwrap();
+            offsets.push(stream.byte_offset());
+        }
+
+        assert_eq!(vec![3, 17], offsets);
+        assert_eq!(17, stream.byte_offset());
+    }
+
+    #[test]
+    fn it_should_stop_on_a_partial_value_and_keep_the_offset() {
+        let input = "[1, 2] {\"a\": [";
+        let mut stream = StreamDeserializer::<JsonType>::new(input);
+
+        assert!(stream.next().unwrap().is_ok());
+        assert_eq!(
+            Some(Err(JsonError::UnexpectedEndOfJson(Position::new(
+                14, 1, 15
+            )))),
+            stream.next()
+        );
+        assert_eq!(None, stream.next());
+        assert_eq!(6, stream.byte_offset());
+
+        let resumed = format!("{}{}", &input[stream.byte_offset()..], "]}");
+        let values: Vec<JsonType> = StreamDeserializer::new(&resumed)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(1, values.len());
+    }
+
+    #[test]
+    fn it_should_continue_after_a_value_of_the_wrong_type() {
+        let results: Vec<Result<u8, _>> = StreamDeserializer::new("1 \"two\" 3").collect();
+
+        assert_eq!(3, results.len());
+        assert!(results[1].is_err());
+        assert_eq!(Ok(3), results[2]);
+    }
+}
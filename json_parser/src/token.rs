@@ -1,2 +1,5 @@
+pub mod source;
+pub mod span;
+#[allow(clippy::module_inception)]
 pub mod token;
 pub mod tokenizer;
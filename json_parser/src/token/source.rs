@@ -0,0 +1,221 @@
+use std::io::{ErrorKind, Read};
+use std::iter::Peekable;
+use std::str::Chars;
+
+use super::span::Position;
+use crate::error::JsonError;
+
+/// Characters read by the [`Tokenizer`](super::tokenizer::Tokenizer)
+pub trait Source {
+    fn next_char(&mut self) -> Option<char>;
+
+    fn peek_char(&mut self) -> Option<&char>;
+
+    /// Returns the error which ended the input early, if any
+    ///
+    /// A source failing to read ends its input and keeps the error until the
+    /// tokenizer asks for it, `position` being where the input stopped.
+    fn take_error(&mut self, position: Position) -> Option<JsonError> {
+        let _ = position;
+        None
+    }
+
+    /// Returns the input between two byte offsets, for sources holding the
+    /// whole input in memory
+    fn slice(&self, start: usize, end: usize) -> Option<&str> {
+        let _ = (start, end);
+        None
+    }
+}
+
+/// The source of a tokenizer reading a string
+#[derive(Debug)]
+pub struct StrSource<'a> {
+    input: &'a str,
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> StrSource<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            chars: input.chars().peekable(),
+        }
+    }
+}
+
+impl Source for StrSource<'_> {
+    fn next_char(&mut self) -> Option<char> {
+        self.chars.next()
+    }
+
+    fn peek_char(&mut self) -> Option<&char> {
+        self.chars.peek()
+    }
+
+    fn slice(&self, start: usize, end: usize) -> Option<&str> {
+        self.input.get(start..end)
+    }
+}
+
+const BUFFER_SIZE: usize = 8 * 1024;
+
+#[derive(Debug)]
+enum ReadError {
+    Io(String),
+    InvalidUtf8,
+}
+
+/// Decodes the UTF-8 bytes of a [`Read`] through an internal buffer
+///
+/// Characters split across two reads are put back together before decoding.
+#[derive(Debug)]
+pub struct ReaderSource<R> {
+    reader: R,
+    buffer: Box<[u8]>,
+    start: usize,
+    end: usize,
+    peeked: Option<char>,
+    error: Option<ReadError>,
+}
+
+impl<R: Read> ReaderSource<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: vec![0; BUFFER_SIZE].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            peeked: None,
+            error: None,
+        }
+    }
+
+    /// Moves the unread bytes to the front of the buffer and reads more after
+    /// them, returns `false` at the end of the input
+    fn fill(&mut self) -> bool {
+        self.buffer.copy_within(self.start..self.end, 0);
+        self.end -= self.start;
+        self.start = 0;
+        loop {
+            match self.reader.read(&mut self.buffer[self.end..]) {
+                Ok(0) => return false,
+                Ok(read) => {
+                    self.end += read;
+                    return true;
+                }
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) => {
+                    self.error = Some(ReadError::Io(error.to_string()));
+                    return false;
+                }
+            }
+        }
+    }
+
+    fn decode(&mut self) -> Option<char> {
+        if self.error.is_some() || (self.start == self.end && !self.fill()) {
+            return None;
+        }
+        let width = match self.buffer[self.start] {
+            0x00..=0x7F => 1,
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => 0,
+        };
+        while self.end - self.start < width {
+            if !self.fill() {
+                break;
+            }
+        }
+        let bytes = &self.buffer[self.start..self.end.min(self.start + width)];
+        match std::str::from_utf8(bytes) {
+            Ok(decoded) if width > 0 && bytes.len() == width => {
+                self.start += width;
+                decoded.chars().next()
+            }
+            _ => {
+                self.error.get_or_insert(ReadError::InvalidUtf8);
+                None
+            }
+        }
+    }
+}
+
+impl<R: Read> Source for ReaderSource<R> {
+    fn next_char(&mut self) -> Option<char> {
+        self.peeked.take().or_else(|| self.decode())
+    }
+
+    fn peek_char(&mut self) -> Option<&char> {
+        if self.peeked.is_none() {
+            self.peeked = self.decode();
+        }
+        self.peeked.as_ref()
+    }
+
+    fn take_error(&mut self, position: Position) -> Option<JsonError> {
+        match self.error.take()? {
+            ReadError::Io(message) => Some(JsonError::IoError(message)),
+            ReadError::InvalidUtf8 => Some(JsonError::InvalidUtf8(position)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hands out its bytes a few at a time
+    struct Chunked<'a> {
+        bytes: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+            let read = self.chunk.min(self.bytes.len()).min(buffer.len());
+            buffer[..read].copy_from_slice(&self.bytes[..read]);
+            self.bytes = &self.bytes[read..];
+            Ok(read)
+        }
+    }
+
+    #[test]
+    fn it_should_decode_characters_split_across_reads() {
+        let text = "aé😀€z";
+        let mut source = ReaderSource::new(Chunked {
+            bytes: text.as_bytes(),
+            chunk: 1,
+        });
+
+        let mut decoded = String::new();
+        while let Some(c) = source.next_char() {
+            decoded.push(c);
+        }
+
+        assert_eq!(text, decoded);
+        assert_eq!(None, source.take_error(Position::default()));
+    }
+
+    #[test]
+    fn it_should_report_invalid_utf8() {
+        let mut source = ReaderSource::new(&b"a\xffb"[..]);
+
+        assert_eq!(Some('a'), source.next_char());
+        assert_eq!(None, source.next_char());
+        assert_eq!(
+            Some(JsonError::InvalidUtf8(Position::new(1, 1, 2))),
+            source.take_error(Position::new(1, 1, 2))
+        );
+    }
+
+    #[test]
+    fn it_should_report_truncated_utf8() {
+        let mut source = ReaderSource::new(&b"\xe2\x82"[..]);
+
+        assert_eq!(None, source.next_char());
+        assert!(source.take_error(Position::default()).is_some());
+    }
+}
@@ -1,7 +1,9 @@
 use std::fmt::{Display, Formatter};
 
+use super::span::Span;
+
 /// Represents a JSON token (brackets, values, or separators)
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Token {
     OpenCurlybracket,
     CloseCurlybracket,
@@ -11,25 +13,47 @@ pub enum Token {
     Colon,
     Str(String),
     Int(i64),
+    UInt(u64),
     Float(f64),
+    Decimal(String),
     Bool(bool),
+    Null,
+    /// An unquoted object key, only read in JSON5
+    Identifier(String),
 }
 
 impl Display for Token {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         let msg = match self {
-            Token::OpenCurlybracket => format!("}}"),
-            Token::CloseCurlybracket => format!("{{"),
-            Token::OpenBracket => format!("["),
-            Token::CloseBracket => format!("]"),
-            Token::Comma => format!(":"),
-            Token::Colon => format!(","),
+            Token::OpenCurlybracket => "{".to_string(),
+            Token::CloseCurlybracket => "}".to_string(),
+            Token::OpenBracket => "[".to_string(),
+            Token::CloseBracket => "]".to_string(),
+            Token::Comma => ",".to_string(),
+            Token::Colon => ":".to_string(),
             Token::Str(value) => format!("String: {}", value),
             Token::Int(value) => format!("Num: {}", value),
+            Token::UInt(value) => format!("Num: {}", value),
             Token::Float(value) => format!("Num: {}", value),
+            Token::Decimal(value) => format!("Num: {}", value),
             Token::Bool(value) => format!("Bool {}", value),
+            Token::Null => "null".to_string(),
+            Token::Identifier(value) => format!("Identifier: {}", value),
         };
 
         write!(f, "{}", msg)
     }
 }
+
+/// A token along with the place it was read from
+#[derive(Debug, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
+
+impl SpannedToken {
+    pub fn new(token: Token, span: Span) -> Self {
+        Self { token, span }
+    }
+}
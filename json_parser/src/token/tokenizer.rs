@@ -1,136 +1,673 @@
+use std::io::Read;
 use std::iter::Iterator;
-use std::iter::Peekable;
-use std::str::Chars;
 
-pub use super::token::Token;
+use crate::error::JsonError;
+use crate::options::{NumberOverflow, ParserOptions};
+
+pub use super::source::{ReaderSource, Source, StrSource};
+pub use super::span::{Position, Span};
+pub use super::token::{SpannedToken, Token};
 
 /// Tokenizes JSON input into a stream of tokens
+///
+/// The tokenizer keeps track of its position in the input so every token
+/// and every error can point back to the line and column it comes from.
+/// It reads a string by default, or any [`Read`] through
+/// [`Tokenizer::from_reader`].
 #[derive(Debug)]
-pub struct Tokenizer<'a> {
-    json: Peekable<Chars<'a>>,
+pub struct Tokenizer<S> {
+    json: S,
+    position: Position,
+    options: ParserOptions,
+    input_too_large: bool,
+    /// A malformed comment found while skipping whitespace
+    comment_error: Option<JsonError>,
+    /// Whether strings and identifiers without escape sequences are left
+    /// empty, to be read from the input at their span
+    borrow_strings: bool,
 }
 
-impl<'a> Tokenizer<'a> {
-    pub fn new(json: &'a str) -> Tokenizer<'a> {
+impl<'a> Tokenizer<StrSource<'a>> {
+    pub fn new(json: &'a str) -> Tokenizer<StrSource<'a>> {
+        Self::with_options(json, ParserOptions::default())
+    }
+
+    pub fn with_options(json: &'a str, options: ParserOptions) -> Tokenizer<StrSource<'a>> {
         Tokenizer {
-            json: json.chars().peekable(),
+            json: StrSource::new(json),
+            position: Position::default(),
+            options,
+            input_too_large: false,
+            comment_error: None,
+            borrow_strings: false,
         }
     }
+
+    /// Leaves the strings and identifiers without escape sequences empty
+    /// instead of copying them, the caller borrowing them from the input
+    pub(crate) fn borrow_strings(&mut self) {
+        self.borrow_strings = true;
+    }
 }
 
-impl<'a> Iterator for Tokenizer<'a> {
-    type Item = Token;
+impl<R: Read> Tokenizer<ReaderSource<R>> {
+    /// Tokenizes the UTF-8 input of `reader` without loading it in memory
+    ///
+    /// Failing reads and invalid UTF-8 end the tokens with an error.
+    pub fn from_reader(reader: R) -> Self {
+        Self::from_reader_with_options(reader, ParserOptions::default())
+    }
+
+    pub fn from_reader_with_options(reader: R, options: ParserOptions) -> Self {
+        Tokenizer {
+            json: ReaderSource::new(reader),
+            position: Position::default(),
+            options,
+            input_too_large: false,
+            comment_error: None,
+            borrow_strings: false,
+        }
+    }
+}
+
+impl<S: Source> Iterator for Tokenizer<S> {
+    type Item = Result<SpannedToken, JsonError>;
 
     /// Returns the next token from the JSON input, skipping whitespace
+    ///
+    /// Malformed input yields an `Err` instead of a token.
     fn next(&mut self) -> Option<Self::Item> {
-        while let Some(token) = self.json.next() {
-            match token {
-                '{' => return Some(Token::OpenCurlybracket),
-                '}' => return Some(Token::CloseCurlybracket),
-                '[' => return Some(Token::OpenBracket),
-                ']' => return Some(Token::CloseBracket),
-                ',' => return Some(Token::Comma),
-                ':' => return Some(Token::Colon),
-                ' ' => continue,
-                '\t' => continue,
-                '\n' => continue,
-                _ => return Some(self.parse_complex_token(token)),
-            }
+        self.skip_whitespace();
+        if let Some(error) = self.comment_error.take() {
+            return Some(Err(error));
+        }
+        let start = self.position;
+        let Some(next_char) = self.bump() else {
+            return self.take_error().map(Err);
+        };
+        let token = match next_char {
+            '{' => Ok(Token::OpenCurlybracket),
+            '}' => Ok(Token::CloseCurlybracket),
+            '[' => Ok(Token::OpenBracket),
+            ']' => Ok(Token::CloseBracket),
+            ',' => Ok(Token::Comma),
+            ':' => Ok(Token::Colon),
+            token => self.parse_complex_token(token, start),
+        };
+        if let Some(error) = self.take_error() {
+            return Some(Err(error));
         }
-        None
+        Some(token.map(|token| SpannedToken::new(token, Span::new(start, self.position))))
     }
 }
 
-impl Tokenizer<'_> {
+impl<S: Source> Tokenizer<S> {
+    /// Returns the position of the next character to be read
+    ///
+    /// Once the input is exhausted this is the end of the input.
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
+    }
+
     /// Checks if the next non-whitespace token is a closing curly bracket
     pub fn is_next_token_closing_curly_bracket(&mut self) -> bool {
-        while let Some(peek) = self.json.peek() {
-            match peek {
-                ' ' | '\t' | '\n' => {
-                    self.json.next();
-                    continue;
+        self.skip_whitespace();
+        self.peek() == Some('}')
+    }
+
+    /// Checks if the next non-whitespace token is a closing bracket
+    pub fn is_next_token_closing_bracket(&mut self) -> bool {
+        self.skip_whitespace();
+        self.peek() == Some(']')
+    }
+
+    /// Returns the next character without consuming it
+    ///
+    /// The input ends early at `max_input_size`, the tokenizer then returns
+    /// `JsonError::InputTooLarge`.
+    fn peek(&mut self) -> Option<char> {
+        let next_char = *self.json.peek_char()?;
+        if let Some(max) = self.options.max_input_size
+            && self.position.offset + next_char.len_utf8() > max
+        {
+            self.input_too_large = true;
+            return None;
+        }
+        Some(next_char)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let next_char = self.peek()?;
+        self.json.next_char();
+        self.position.advance(next_char);
+        Some(next_char)
+    }
+
+    /// Returns the input read since the byte offset `start`, only called when
+    /// strings are borrowed
+    fn borrowed(&self, start: usize) -> &str {
+        self.json
+            .slice(start, self.position.offset)
+            .expect("strings are only borrowed from a source holding the input")
+    }
+
+    /// Returns the error which ended the input early, if any
+    fn take_error(&mut self) -> Option<JsonError> {
+        if self.input_too_large {
+            return Some(JsonError::InputTooLarge(self.position));
+        }
+        self.json.take_error(self.position)
+    }
+
+    /// Skips whitespace, and comments in JSON5
+    fn skip_whitespace(&mut self) {
+        if self.options.json5 {
+            return self.skip_json5_whitespace();
+        }
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.bump();
+        }
+    }
+
+    fn skip_json5_whitespace(&mut self) {
+        while self.comment_error.is_none() {
+            match self.peek() {
+                Some(c) if c.is_whitespace() || c == '\u{FEFF}' => {
+                    self.bump();
                 }
-                '}' => return true,
-                _ => return false,
+                Some('/') => {
+                    let start = self.position;
+                    self.bump();
+                    match self.bump() {
+                        Some('/') => {
+                            while let Some(c) = self.bump() {
+                                if c == '\n' {
+                                    break;
+                                }
+                            }
+                        }
+                        Some('*') => self.skip_block_comment(start),
+                        _ => self.comment_error = Some(JsonError::UnexpectedCharacter('/', start)),
+                    }
+                }
+                _ => return,
             }
         }
-        false
     }
 
-    fn parse_complex_token(&mut self, token: char) -> Token {
+    /// Skips a `/* */` comment up to its end, the opening `/*` being consumed
+    fn skip_block_comment(&mut self, start: Position) {
+        let mut star = false;
+        loop {
+            match self.bump() {
+                Some('/') if star => return,
+                Some(c) => star = c == '*',
+                None => {
+                    self.comment_error = Some(JsonError::UnterminatedComment(start));
+                    return;
+                }
+            }
+        }
+    }
+
+    fn parse_complex_token(&mut self, token: char, start: Position) -> Result<Token, JsonError> {
+        if self.options.json5 {
+            return self.parse_json5_token(token, start);
+        }
         if token == '"' {
-            return self.parse_string();
-        } else if token.is_numeric() {
-            return self.parse_numeric(token);
-        } else if token == 'f' || token == 't' {
-            return self.parse_bool(token);
+            self.parse_string(token, start)
+        } else if token.is_ascii_digit() || token == '-' {
+            self.parse_numeric(token, start)
+        } else if token == 'f' || token == 't' || token == 'n' {
+            self.parse_keyword(token, start)
         } else {
-            panic!("Error: unknown char: |{}|", token);
+            Err(JsonError::UnexpectedCharacter(token, start))
+        }
+    }
+
+    /// Parses the tokens JSON5 adds along with the JSON ones
+    fn parse_json5_token(&mut self, token: char, start: Position) -> Result<Token, JsonError> {
+        match token {
+            '"' | '\'' => self.parse_string(token, start),
+            '0'..='9' | '-' | '+' | '.' => self.parse_json5_numeric(token, start),
+            c if c.is_alphabetic() || c == '_' || c == '$' => {
+                let mut identifier = String::new();
+                if !self.borrow_strings {
+                    identifier.push(c);
+                }
+                while let Some(c) = self.peek() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '$') {
+                        break;
+                    }
+                    if !self.borrow_strings {
+                        identifier.push(c);
+                    }
+                    self.bump();
+                }
+                let text = if self.borrow_strings {
+                    self.borrowed(start.offset)
+                } else {
+                    identifier.as_str()
+                };
+                Ok(match text {
+                    "true" => Token::Bool(true),
+                    "false" => Token::Bool(false),
+                    "null" => Token::Null,
+                    "Infinity" => Token::Float(f64::INFINITY),
+                    "NaN" => Token::Float(f64::NAN),
+                    _ => Token::Identifier(identifier),
+                })
+            }
+            _ => Err(JsonError::UnexpectedCharacter(token, start)),
         }
     }
 
-    fn parse_string(&mut self) -> Token {
+    /// Parses a JSON5 number, which may also be hexadecimal, `Infinity` or
+    /// `NaN`, start with `+` and start or end with a decimal point
+    fn parse_json5_numeric(&mut self, token: char, start: Position) -> Result<Token, JsonError> {
+        let mut raw = token.to_string();
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-')) {
+                break;
+            }
+            raw.push(c);
+            self.bump();
+        }
+        let invalid = |raw: &str| JsonError::InvalidNumber(raw.to_string(), start);
+        let (negative, body) = match raw.strip_prefix('-') {
+            Some(body) => (true, body),
+            None => (false, raw.strip_prefix('+').unwrap_or(&raw)),
+        };
+        let sign = if negative { "-" } else { "" };
+
+        match body {
+            "Infinity" if negative => return Ok(Token::Float(f64::NEG_INFINITY)),
+            "Infinity" => return Ok(Token::Float(f64::INFINITY)),
+            "NaN" => return Ok(Token::Float(f64::NAN)),
+            _ => {}
+        }
+        if let Some(hex) = body.strip_prefix("0x").or_else(|| body.strip_prefix("0X")) {
+            if hex.is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(invalid(&raw));
+            }
+            // Larger numbers go through the overflow policy as decimal digits
+            let decimal = format!("{}{}", sign, hex_to_decimal(hex));
+            return self
+                .convert_number(decimal, false, start)
+                .map_err(|_| JsonError::NumberOverflow(raw, start));
+        }
+
+        // Rewrites the number following the JSON grammar before converting it
+        let (mantissa, exponent) = match body.find(['e', 'E']) {
+            Some(index) => (&body[..index], Some(&body[index + 1..])),
+            None => (body, None),
+        };
+        let (integer, fraction) = match mantissa.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (mantissa, None),
+        };
+        let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        let exponent_digits = exponent.map(|exponent| {
+            exponent
+                .strip_prefix(['+', '-'])
+                .unwrap_or(exponent)
+                .to_string()
+        });
+        let valid = digits(integer)
+            && fraction.is_none_or(digits)
+            && !(integer.is_empty() && fraction.is_none_or(str::is_empty))
+            && !(integer.len() > 1 && integer.starts_with('0'))
+            && exponent_digits.is_none_or(|exponent| !exponent.is_empty() && digits(&exponent));
+        if !valid {
+            return Err(invalid(&raw));
+        }
+        let mut numeric_string =
+            format!("{}{}", sign, if integer.is_empty() { "0" } else { integer });
+        if let Some(fraction) = fraction {
+            numeric_string.push('.');
+            numeric_string.push_str(if fraction.is_empty() { "0" } else { fraction });
+        }
+        if let Some(exponent) = exponent {
+            numeric_string.push('e');
+            numeric_string.push_str(exponent);
+        }
+        let is_float = fraction.is_some() || exponent.is_some();
+        self.convert_number(numeric_string, is_float, start)
+    }
+
+    /// Parses a string up to its closing `quote`, decoding escape sequences
+    ///
+    /// When strings are borrowed nothing is copied before the first escape
+    /// sequence, so a string without any is returned empty.
+    fn parse_string(&mut self, quote: char, start: Position) -> Result<Token, JsonError> {
         let mut string_token = String::new();
-        while let Some(next_char) = self.json.next() {
-            if next_char == '"' {
-                return Token::Str(string_token);
+        let content_start = self.position.offset;
+        let mut copying = !self.borrow_strings;
+        loop {
+            let length = if copying {
+                string_token.len()
+            } else {
+                self.position.offset - content_start
+            };
+            if let Some(max) = self.options.max_string_length
+                && length > max
+            {
+                return Err(self.skip_string(quote, JsonError::StringTooLong(max, start)));
+            }
+            let char_position = self.position;
+            let next_char = self.bump();
+            if !copying && next_char == Some('\\') {
+                string_token.push_str(
+                    self.json
+                        .slice(content_start, char_position.offset)
+                        .expect("strings are only borrowed from a source holding the input"),
+                );
+                copying = true;
+            }
+            match next_char {
+                Some(c) if c == quote => return Ok(Token::Str(string_token)),
+                Some('\\') => match self.parse_escape(start, char_position) {
+                    Ok(escaped) => string_token.extend(escaped),
+                    Err(error) => return Err(self.skip_string(quote, error)),
+                },
+                // The line break ends the string as `skip_string` would, which
+                // would skip the next line instead if called past it
+                Some(next_char @ ('\n' | '\r')) => {
+                    return Err(JsonError::ControlCharacter(next_char, char_position));
+                }
+                Some(next_char) if next_char < '\u{20}' && !self.options.json5 => {
+                    let error = JsonError::ControlCharacter(next_char, char_position);
+                    return Err(self.skip_string(quote, error));
+                }
+                Some(next_char) if copying => string_token.push(next_char),
+                Some(_) => {}
+                None => return Err(JsonError::UnterminatedString(start)),
+            }
+        }
+    }
+
+    /// Skips the rest of a malformed string up to its closing quote, or up to
+    /// the end of the line as a string cannot span lines, so the tokens after
+    /// it can still be read
+    fn skip_string(&mut self, quote: char, error: JsonError) -> JsonError {
+        while let Some(next_char) = self.peek() {
+            if next_char == '\n' || next_char == '\r' {
+                break;
+            }
+            self.bump();
+            if next_char == quote {
+                break;
+            }
+            if next_char == '\\' {
+                self.bump();
             }
-            string_token.push(next_char)
         }
-        panic!("Error: string does not end with \" token");
+        error
     }
 
-    fn parse_numeric(&mut self, token: char) -> Token {
+    /// Decodes the escape sequence following a backslash, `None` being a
+    /// JSON5 line continuation
+    fn parse_escape(
+        &mut self,
+        string_start: Position,
+        escape_start: Position,
+    ) -> Result<Option<char>, JsonError> {
+        let escaped = match self.bump() {
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some('/') => '/',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('u') => self.parse_unicode_escape(escape_start)?,
+            Some(escaped) if self.options.json5 => {
+                return self.parse_json5_escape(escaped, escape_start);
+            }
+            Some(escaped) => return Err(JsonError::InvalidEscape(escaped, escape_start)),
+            None => return Err(JsonError::UnterminatedString(string_start)),
+        };
+        Ok(Some(escaped))
+    }
+
+    /// Decodes the escape sequences JSON5 adds, any other character which is
+    /// not a digit standing for itself
+    fn parse_json5_escape(
+        &mut self,
+        escaped: char,
+        escape_start: Position,
+    ) -> Result<Option<char>, JsonError> {
+        match escaped {
+            'v' => Ok(Some('\u{b}')),
+            '0' if !self.peek().is_some_and(|c| c.is_ascii_digit()) => Ok(Some('\0')),
+            'x' => {
+                let mut hex = String::new();
+                for _ in 0..2 {
+                    match self.peek() {
+                        Some(digit) if digit.is_ascii_hexdigit() => {
+                            hex.push(digit);
+                            self.bump();
+                        }
+                        _ => return Err(JsonError::InvalidUnicodeEscape(hex, escape_start)),
+                    }
+                }
+                let code = u8::from_str_radix(&hex, 16)
+                    .map_err(|_| JsonError::InvalidUnicodeEscape(hex, escape_start))?;
+                Ok(Some(char::from(code)))
+            }
+            '\r' => {
+                if self.peek() == Some('\n') {
+                    self.bump();
+                }
+                Ok(None)
+            }
+            '\n' | '\u{2028}' | '\u{2029}' => Ok(None),
+            '0'..='9' => Err(JsonError::InvalidEscape(escaped, escape_start)),
+            escaped => Ok(Some(escaped)),
+        }
+    }
+
+    /// Decodes `\uXXXX`, combining UTF-16 surrogate pairs into a single char
+    fn parse_unicode_escape(&mut self, escape_start: Position) -> Result<char, JsonError> {
+        let code = self.parse_hex_code(escape_start)?;
+        if !(0xD800..=0xDBFF).contains(&code) {
+            // Only surrogates fail to convert, a low surrogate cannot come first
+            return char::from_u32(code as u32).ok_or(JsonError::LoneSurrogate(code, escape_start));
+        }
+
+        let low_start = self.position;
+        if self.peek() != Some('\\') {
+            return Err(JsonError::LoneSurrogate(code, escape_start));
+        }
+        self.bump();
+        if self.peek() != Some('u') {
+            return Err(JsonError::LoneSurrogate(code, escape_start));
+        }
+        self.bump();
+        let low = self.parse_hex_code(low_start)?;
+        char::decode_utf16([code, low])
+            .next()
+            .and_then(|decoded| decoded.ok())
+            .ok_or(JsonError::LoneSurrogate(code, escape_start))
+    }
+
+    /// Reads the four hexadecimal digits of a `\u` escape
+    fn parse_hex_code(&mut self, escape_start: Position) -> Result<u16, JsonError> {
+        let mut hex = String::new();
+        for _ in 0..4 {
+            match self.peek() {
+                Some(digit) if digit.is_ascii_hexdigit() => {
+                    hex.push(digit);
+                    self.bump();
+                }
+                _ => return Err(JsonError::InvalidUnicodeEscape(hex, escape_start)),
+            }
+        }
+        u16::from_str_radix(&hex, 16)
+            .map_err(|_| JsonError::InvalidUnicodeEscape(hex, escape_start))
+    }
+
+    /// Parses a number following the RFC 8259 grammar:
+    /// `-? (0 | [1-9][0-9]*) (.[0-9]+)? ([eE][+-]?[0-9]+)?`
+    fn parse_numeric(&mut self, token: char, start: Position) -> Result<Token, JsonError> {
         let mut numeric_string = String::new();
         numeric_string.push(token);
+        let mut first_digit = token;
+        if token == '-' {
+            match self.peek() {
+                Some(digit) if digit.is_ascii_digit() => {
+                    numeric_string.push(digit);
+                    self.bump();
+                    first_digit = digit;
+                }
+                _ => return Err(self.invalid_number(numeric_string, start)),
+            }
+        }
+        // A leading zero is a number on its own, so `01` is rejected as trailing digits
+        if first_digit != '0' {
+            self.push_digits(&mut numeric_string);
+        }
+
         let mut is_float = false;
-        while let Some(peek_char) = self.json.peek() {
-            if peek_char.is_numeric() {
-                numeric_string.push(*peek_char);
-            } else if *peek_char == '.' {
-                numeric_string.push(*peek_char);
-                is_float = true;
-            } else {
+        if self.peek() == Some('.') {
+            is_float = true;
+            numeric_string.push('.');
+            self.bump();
+            if self.push_digits(&mut numeric_string) == 0 {
+                return Err(self.invalid_number(numeric_string, start));
+            }
+        }
+        if let Some(exponent @ ('e' | 'E')) = self.peek() {
+            is_float = true;
+            numeric_string.push(exponent);
+            self.bump();
+            if let Some(sign @ ('+' | '-')) = self.peek() {
+                numeric_string.push(sign);
+                self.bump();
+            }
+            if self.push_digits(&mut numeric_string) == 0 {
+                return Err(self.invalid_number(numeric_string, start));
+            }
+        }
+        if let Some('0'..='9' | '.' | 'e' | 'E' | '+' | '-') = self.peek() {
+            return Err(self.invalid_number(numeric_string, start));
+        }
+
+        self.convert_number(numeric_string, is_float, start)
+    }
+
+    /// Consumes consecutive digits, returns how many were read
+    fn push_digits(&mut self, numeric_string: &mut String) -> usize {
+        let mut count = 0;
+        while let Some(digit) = self.peek() {
+            if !digit.is_ascii_digit() {
                 break;
             }
-            self.json.next();
+            numeric_string.push(digit);
+            self.bump();
+            count += 1;
         }
+        count
+    }
 
-        if is_float == true {
-            let number = numeric_string
-                .parse::<f64>()
-                .expect("Error: string is not a number");
-            return Token::Float(number);
-        } else {
-            let number = numeric_string
-                .parse::<i64>()
-                .expect("Error: string is not a number");
-            return Token::Int(number);
+    /// Builds an `InvalidNumber` error out of everything that looks like part of the number
+    fn invalid_number(&mut self, mut numeric_string: String, start: Position) -> JsonError {
+        while let Some(next_char) = self.peek() {
+            if !matches!(next_char, '0'..='9' | '.' | 'e' | 'E' | '+' | '-') {
+                break;
+            }
+            numeric_string.push(next_char);
+            self.bump();
         }
+        JsonError::InvalidNumber(numeric_string, start)
     }
 
-    fn parse_bool(&mut self, token: char) -> Token {
-        let mut boolean_str = token.to_string();
-        let bool_letters = vec!['a', 'l', 's', 'e', 'r', 'u'];
-        while let Some(next_char) = self.json.peek() {
-            if bool_letters.contains(&next_char) {
-                boolean_str.push(*next_char);
-                self.json.next();
+    /// Picks the narrowest representation of a number, applying the overflow
+    /// policy when it fits neither `i64`, `u64` nor a finite `f64`
+    ///
+    /// `-0` is a float so its sign survives a round trip.
+    fn convert_number(
+        &self,
+        numeric_string: String,
+        is_float: bool,
+        start: Position,
+    ) -> Result<Token, JsonError> {
+        if numeric_string == "-0" {
+            return Ok(Token::Float(-0.0));
+        }
+        if !is_float {
+            if let Ok(number) = numeric_string.parse::<i64>() {
+                return Ok(Token::Int(number));
+            }
+            if let Ok(number) = numeric_string.parse::<u64>() {
+                return Ok(Token::UInt(number));
+            }
+        } else if let Ok(number) = numeric_string.parse::<f64>()
+            && number.is_finite()
+        {
+            return Ok(Token::Float(number));
+        }
+
+        match self.options.number_overflow {
+            NumberOverflow::Error => Err(JsonError::NumberOverflow(numeric_string, start)),
+            NumberOverflow::Float => match numeric_string.parse::<f64>() {
+                Ok(number) if number.is_finite() => Ok(Token::Float(number)),
+                _ => Err(JsonError::NumberOverflow(numeric_string, start)),
+            },
+            NumberOverflow::Decimal => Ok(Token::Decimal(numeric_string)),
+        }
+    }
+
+    /// Parses the literals `true`, `false` and `null`
+    fn parse_keyword(&mut self, token: char, start: Position) -> Result<Token, JsonError> {
+        let mut keyword = token.to_string();
+        while let Some(next_char) = self.peek() {
+            if next_char.is_ascii_alphabetic() {
+                keyword.push(next_char);
+                self.bump();
             } else {
                 break;
             }
         }
-        if boolean_str == "true" {
-            return Token::Bool(true);
-        } else if boolean_str == "false" {
-            return Token::Bool(false);
+        match keyword.as_str() {
+            "true" => Ok(Token::Bool(true)),
+            "false" => Ok(Token::Bool(false)),
+            "null" => Ok(Token::Null),
+            _ => Err(JsonError::InvalidLiteral(keyword, start)),
         }
-        Token::Str(boolean_str)
     }
 }
 
-#[cfg(test)]
+/// Converts hexadecimal digits to decimal ones, however many there are
+fn hex_to_decimal(hex: &str) -> String {
+    const LIMB: u64 = 1_000_000_000;
+    // Groups of nine decimal digits, the least significant first
+    let mut limbs = vec![0];
+    for digit in hex.chars() {
+        let mut carry = u64::from(digit.to_digit(16).expect("a hexadecimal digit"));
+        for limb in &mut limbs {
+            let value = *limb * 16 + carry;
+            *limb = value % LIMB;
+            carry = value / LIMB;
+        }
+        if carry > 0 {
+            limbs.push(carry);
+        }
+    }
+    let mut limbs = limbs.into_iter().rev();
+    let mut decimal = limbs.next().unwrap_or_default().to_string();
+    for limb in limbs {
+        decimal.push_str(&format!("{:09}", limb));
+    }
+    decimal
+}
 
+#[cfg(test)]
 mod tests {
     use super::*;
 
@@ -139,11 +676,20 @@ mod tests {
         let json = r#" { } [ ]  ,"#;
         let mut tokenizer = Tokenizer::new(json);
 
-        assert_eq!(Token::OpenCurlybracket, tokenizer.next().unwrap());
-        assert_eq!(Token::CloseCurlybracket, tokenizer.next().unwrap());
-        assert_eq!(Token::OpenBracket, tokenizer.next().unwrap());
-        assert_eq!(Token::CloseBracket, tokenizer.next().unwrap());
-        assert_eq!(Token::Comma, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::OpenCurlybracket,
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(
+            Token::CloseCurlybracket,
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::OpenBracket, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(
+            Token::CloseBracket,
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Comma, tokenizer.next().unwrap().unwrap().token);
         assert_eq!(None, tokenizer.next());
     }
 
@@ -160,38 +706,68 @@ mod tests {
 }"#;
         let mut tokenizer = Tokenizer::new(json);
 
-        assert_eq!(Token::OpenCurlybracket, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::OpenCurlybracket,
+            tokenizer.next().unwrap().unwrap().token
+        );
 
-        assert_eq!(Token::Str("key1".to_string()), tokenizer.next().unwrap());
-        assert_eq!(Token::Colon, tokenizer.next().unwrap());
-        assert_eq!(Token::Str("value1".to_string()), tokenizer.next().unwrap());
-        assert_eq!(Token::Comma, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::Str("key1".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Colon, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(
+            Token::Str("value1".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Comma, tokenizer.next().unwrap().unwrap().token);
 
-        assert_eq!(Token::Str("key2".to_string()), tokenizer.next().unwrap());
-        assert_eq!(Token::Colon, tokenizer.next().unwrap());
-        assert_eq!(Token::Int(5), tokenizer.next().unwrap());
-        assert_eq!(Token::Comma, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::Str("key2".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Colon, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Int(5), tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Comma, tokenizer.next().unwrap().unwrap().token);
 
-        assert_eq!(Token::Str("key3".to_string()), tokenizer.next().unwrap());
-        assert_eq!(Token::Colon, tokenizer.next().unwrap());
-        assert_eq!(Token::Float(1.1), tokenizer.next().unwrap());
-        assert_eq!(Token::Comma, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::Str("key3".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Colon, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Float(1.1), tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Comma, tokenizer.next().unwrap().unwrap().token);
 
-        assert_eq!(Token::Str("key4".to_string()), tokenizer.next().unwrap());
-        assert_eq!(Token::Colon, tokenizer.next().unwrap());
-        assert_eq!(Token::Float(15.13), tokenizer.next().unwrap());
-        assert_eq!(Token::Comma, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::Str("key4".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Colon, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(
+            Token::Float(15.13),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Comma, tokenizer.next().unwrap().unwrap().token);
 
-        assert_eq!(Token::Str("key5".to_string()), tokenizer.next().unwrap());
-        assert_eq!(Token::Colon, tokenizer.next().unwrap());
-        assert_eq!(Token::Bool(false), tokenizer.next().unwrap());
-        assert_eq!(Token::Comma, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::Str("key5".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Colon, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Bool(false), tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Comma, tokenizer.next().unwrap().unwrap().token);
 
-        assert_eq!(Token::Str("key6".to_string()), tokenizer.next().unwrap());
-        assert_eq!(Token::Colon, tokenizer.next().unwrap());
-        assert_eq!(Token::Bool(true), tokenizer.next().unwrap());
+        assert_eq!(
+            Token::Str("key6".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Colon, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Bool(true), tokenizer.next().unwrap().unwrap().token);
 
-        assert_eq!(Token::CloseCurlybracket, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::CloseCurlybracket,
+            tokenizer.next().unwrap().unwrap().token
+        );
 
         assert_eq!(None, tokenizer.next());
     }
@@ -208,29 +784,53 @@ mod tests {
 }"#;
         let mut tokenizer = Tokenizer::new(json);
 
-        assert_eq!(Token::OpenCurlybracket, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::OpenCurlybracket,
+            tokenizer.next().unwrap().unwrap().token
+        );
 
-        assert_eq!(Token::Str("key1".to_string()), tokenizer.next().unwrap());
-        assert_eq!(Token::Colon, tokenizer.next().unwrap());
-        assert_eq!(Token::Int(5), tokenizer.next().unwrap());
-        assert_eq!(Token::Comma, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::Str("key1".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Colon, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Int(5), tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Comma, tokenizer.next().unwrap().unwrap().token);
 
-        assert_eq!(Token::Str("key2".to_string()), tokenizer.next().unwrap());
-        assert_eq!(Token::Colon, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::Str("key2".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Colon, tokenizer.next().unwrap().unwrap().token);
 
-        assert_eq!(Token::OpenCurlybracket, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::OpenCurlybracket,
+            tokenizer.next().unwrap().unwrap().token
+        );
 
-        assert_eq!(Token::Str("key21".to_string()), tokenizer.next().unwrap());
-        assert_eq!(Token::Colon, tokenizer.next().unwrap());
-        assert_eq!(Token::Int(15), tokenizer.next().unwrap());
-        assert_eq!(Token::Comma, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::Str("key21".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Colon, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Int(15), tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Comma, tokenizer.next().unwrap().unwrap().token);
 
-        assert_eq!(Token::Str("key22".to_string()), tokenizer.next().unwrap());
-        assert_eq!(Token::Colon, tokenizer.next().unwrap());
-        assert_eq!(Token::Bool(false), tokenizer.next().unwrap());
+        assert_eq!(
+            Token::Str("key22".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Colon, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Bool(false), tokenizer.next().unwrap().unwrap().token);
 
-        assert_eq!(Token::CloseCurlybracket, tokenizer.next().unwrap());
-        assert_eq!(Token::CloseCurlybracket, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::CloseCurlybracket,
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(
+            Token::CloseCurlybracket,
+            tokenizer.next().unwrap().unwrap().token
+        );
 
         assert_eq!(None, tokenizer.next());
     }
@@ -244,26 +844,517 @@ mod tests {
 }"#;
         let mut tokenizer = Tokenizer::new(json);
 
-        assert_eq!(Token::OpenCurlybracket, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::OpenCurlybracket,
+            tokenizer.next().unwrap().unwrap().token
+        );
+
+        assert_eq!(
+            Token::Str("key1".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Colon, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Int(5), tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Comma, tokenizer.next().unwrap().unwrap().token);
 
-        assert_eq!(Token::Str("key1".to_string()), tokenizer.next().unwrap());
-        assert_eq!(Token::Colon, tokenizer.next().unwrap());
-        assert_eq!(Token::Int(5), tokenizer.next().unwrap());
-        assert_eq!(Token::Comma, tokenizer.next().unwrap());
+        assert_eq!(
+            Token::Str("key2".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(Token::Colon, tokenizer.next().unwrap().unwrap().token);
 
-        assert_eq!(Token::Str("key2".to_string()), tokenizer.next().unwrap());
-        assert_eq!(Token::Colon, tokenizer.next().unwrap());
+        assert_eq!(Token::OpenBracket, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Int(1), tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Comma, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Int(2), tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Comma, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Int(3), tokenizer.next().unwrap().unwrap().token);
 
-        assert_eq!(Token::OpenBracket, tokenizer.next().unwrap());
-        assert_eq!(Token::Int(1), tokenizer.next().unwrap());
-        assert_eq!(Token::Comma, tokenizer.next().unwrap());
-        assert_eq!(Token::Int(2), tokenizer.next().unwrap());
-        assert_eq!(Token::Comma, tokenizer.next().unwrap());
-        assert_eq!(Token::Int(3), tokenizer.next().unwrap());
+        assert_eq!(
+            Token::CloseBracket,
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(
+            Token::CloseCurlybracket,
+            tokenizer.next().unwrap().unwrap().token
+        );
+
+        assert_eq!(None, tokenizer.next());
+    }
 
-        assert_eq!(Token::CloseBracket, tokenizer.next().unwrap());
-        assert_eq!(Token::CloseCurlybracket, tokenizer.next().unwrap());
+    #[test]
+    fn it_should_tokenize_null() {
+        let json = r#"[null, true]"#;
+        let mut tokenizer = Tokenizer::new(json);
 
+        assert_eq!(Token::OpenBracket, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Null, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Comma, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(Token::Bool(true), tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(
+            Token::CloseBracket,
+            tokenizer.next().unwrap().unwrap().token
+        );
         assert_eq!(None, tokenizer.next());
     }
+
+    #[test]
+    fn it_should_return_an_error_on_unterminated_string() {
+        let mut tokenizer = Tokenizer::new(r#""hello"#);
+
+        assert_eq!(
+            Some(Err(JsonError::UnterminatedString(Position::new(0, 1, 1)))),
+            tokenizer.next()
+        );
+    }
+
+    #[test]
+    fn it_should_return_an_error_on_invalid_number() {
+        let mut tokenizer = Tokenizer::new("1.2.3");
+
+        assert_eq!(
+            Some(Err(JsonError::InvalidNumber(
+                "1.2.3".to_string(),
+                Position::new(0, 1, 1)
+            ))),
+            tokenizer.next()
+        );
+    }
+
+    #[test]
+    fn it_should_return_an_error_on_unexpected_character() {
+        let mut tokenizer = Tokenizer::new("[@]");
+
+        assert_eq!(Token::OpenBracket, tokenizer.next().unwrap().unwrap().token);
+        assert_eq!(
+            Some(Err(JsonError::UnexpectedCharacter(
+                '@',
+                Position::new(1, 1, 2)
+            ))),
+            tokenizer.next()
+        );
+    }
+
+    #[test]
+    fn it_should_return_an_error_on_invalid_literal() {
+        let mut tokenizer = Tokenizer::new("nul");
+
+        assert_eq!(
+            Some(Err(JsonError::InvalidLiteral(
+                "nul".to_string(),
+                Position::new(0, 1, 1)
+            ))),
+            tokenizer.next()
+        );
+    }
+
+    #[test]
+    fn it_should_attach_a_span_to_each_token() {
+        let json = "{\n  \"key\": 12\n}";
+        let mut tokenizer = Tokenizer::new(json);
+
+        let open = tokenizer.next().unwrap().unwrap();
+        assert_eq!(
+            Span::new(Position::new(0, 1, 1), Position::new(1, 1, 2)),
+            open.span
+        );
+
+        let key = tokenizer.next().unwrap().unwrap();
+        assert_eq!(Token::Str("key".to_string()), key.token);
+        assert_eq!(
+            Span::new(Position::new(4, 2, 3), Position::new(9, 2, 8)),
+            key.span
+        );
+
+        tokenizer.next();
+        let value = tokenizer.next().unwrap().unwrap();
+        assert_eq!(
+            Span::new(Position::new(11, 2, 10), Position::new(13, 2, 12)),
+            value.span
+        );
+
+        let close = tokenizer.next().unwrap().unwrap();
+        assert_eq!(Position::new(14, 3, 1), close.span.start);
+        assert_eq!(None, tokenizer.next());
+        assert_eq!(Position::new(15, 3, 2), tokenizer.position());
+    }
+
+    #[test]
+    fn it_should_decode_string_escapes() {
+        let json = r#""say \"hi\" \\ \/ \b\f\n\r\t""#;
+        let mut tokenizer = Tokenizer::new(json);
+
+        assert_eq!(
+            Token::Str("say \"hi\" \\ / \u{8}\u{c}\n\r\t".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+        assert_eq!(None, tokenizer.next());
+    }
+
+    #[test]
+    fn it_should_decode_unicode_escapes() {
+        let json = r#""caf\u00e9 \uD83D\ude00""#;
+        let mut tokenizer = Tokenizer::new(json);
+
+        assert_eq!(
+            Token::Str("café 😀".to_string()),
+            tokenizer.next().unwrap().unwrap().token
+        );
+    }
+
+    #[test]
+    fn it_should_return_an_error_on_lone_surrogate() {
+        let mut tokenizer = Tokenizer::new(r#""a\uD83Db""#);
+        assert_eq!(
+            Some(Err(JsonError::LoneSurrogate(
+                0xD83D,
+                Position::new(2, 1, 3)
+            ))),
+            tokenizer.next()
+        );
+
+        let mut tokenizer = Tokenizer::new(r#""\uDE00""#);
+        assert_eq!(
+            Some(Err(JsonError::LoneSurrogate(
+                0xDE00,
+                Position::new(1, 1, 2)
+            ))),
+            tokenizer.next()
+        );
+    }
+
+    #[test]
+    fn it_should_return_an_error_on_invalid_escape() {
+        let mut tokenizer = Tokenizer::new(r#""\x""#);
+        assert_eq!(
+            Some(Err(JsonError::InvalidEscape('x', Position::new(1, 1, 2)))),
+            tokenizer.next()
+        );
+
+        let mut tokenizer = Tokenizer::new(r#""\u12G4""#);
+        assert_eq!(
+            Some(Err(JsonError::InvalidUnicodeEscape(
+                "12".to_string(),
+                Position::new(1, 1, 2)
+            ))),
+            tokenizer.next()
+        );
+    }
+
+    #[test]
+    fn it_should_return_an_error_on_raw_control_character() {
+        let mut tokenizer = Tokenizer::new("\"line\nbreak\"");
+
+        assert_eq!(
+            Some(Err(JsonError::ControlCharacter(
+                '\n',
+                Position::new(5, 1, 6)
+            ))),
+            tokenizer.next()
+        );
+    }
+
+    fn tokenize_number(json: &str, options: ParserOptions) -> Result<Token, JsonError> {
+        let mut tokenizer = Tokenizer::with_options(json, options);
+        tokenizer.next().unwrap().map(|spanned| spanned.token)
+    }
+
+    #[test]
+    fn it_should_tokenize_numbers() {
+        let options = ParserOptions::default();
+
+        assert_eq!(Ok(Token::Int(0)), tokenize_number("0", options.clone()));
+        assert_eq!(Ok(Token::Int(-5)), tokenize_number("-5", options.clone()));
+        assert_eq!(
+            Ok(Token::Float(-0.5)),
+            tokenize_number("-0.5", options.clone())
+        );
+        assert_eq!(
+            Ok(Token::Float(1e10)),
+            tokenize_number("1e10", options.clone())
+        );
+        assert_eq!(
+            Ok(Token::Float(2.5e-3)),
+            tokenize_number("2.5E-3", options.clone())
+        );
+        assert_eq!(
+            Ok(Token::Float(3e2)),
+            tokenize_number("3e+2", options.clone())
+        );
+        assert_eq!(
+            Ok(Token::UInt(u64::MAX)),
+            tokenize_number("18446744073709551615", options)
+        );
+    }
+
+    #[test]
+    fn it_should_keep_the_sign_of_negative_zero() {
+        let zero = tokenize_number("-0", ParserOptions::default());
+
+        assert!(
+            matches!(zero, Ok(Token::Float(value)) if value == 0.0 && value.is_sign_negative())
+        );
+        assert_eq!(
+            Ok(Token::Int(0)),
+            tokenize_number("0", ParserOptions::default())
+        );
+    }
+
+    #[test]
+    fn it_should_reject_invalid_number_grammar() {
+        for json in [
+            "01", "-", "1.", "1.2.3", "1e", "1e+", "-01", "1.5e3.2", "--1",
+        ] {
+            assert_eq!(
+                Err(JsonError::InvalidNumber(
+                    json.to_string(),
+                    Position::new(0, 1, 1)
+                )),
+                tokenize_number(json, ParserOptions::default()),
+                "{} should be rejected",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn it_should_apply_the_number_overflow_policy() {
+        let big = "123456789012345678901234567890";
+        let position = Position::new(0, 1, 1);
+
+        assert_eq!(
+            Ok(Token::Float(1.2345678901234568e29)),
+            tokenize_number(big, ParserOptions::default())
+        );
+        assert_eq!(
+            Err(JsonError::NumberOverflow("1e400".to_string(), position)),
+            tokenize_number("1e400", ParserOptions::default())
+        );
+
+        let options = ParserOptions {
+            number_overflow: NumberOverflow::Error,
+            ..ParserOptions::default()
+        };
+        assert_eq!(
+            Err(JsonError::NumberOverflow(big.to_string(), position)),
+            tokenize_number(big, options)
+        );
+
+        let options = ParserOptions {
+            number_overflow: NumberOverflow::Decimal,
+            ..ParserOptions::default()
+        };
+        assert_eq!(
+            Ok(Token::Decimal(big.to_string())),
+            tokenize_number(big, options.clone())
+        );
+        assert_eq!(
+            Ok(Token::Decimal("-1e400".to_string())),
+            tokenize_number("-1e400", options)
+        );
+    }
+
+    #[test]
+    fn it_should_leave_unescaped_strings_empty_when_borrowing() {
+        let mut tokenizer = Tokenizer::with_options(
+            r#"["plain", "a\tb", "toolong"]"#,
+            ParserOptions {
+                max_string_length: Some(5),
+                ..ParserOptions::default()
+            },
+        );
+        tokenizer.borrow_strings();
+
+        let tokens: Vec<_> = tokenizer
+            .map(|token| token.map(|token| token.token))
+            .collect();
+
+        assert_eq!(
+            vec![
+                Ok(Token::OpenBracket),
+                Ok(Token::Str(String::new())),
+                Ok(Token::Comma),
+                Ok(Token::Str("a\tb".to_string())),
+                Ok(Token::Comma),
+                Err(JsonError::StringTooLong(5, Position::new(18, 1, 19))),
+                Ok(Token::CloseBracket),
+            ],
+            tokens
+        );
+    }
+
+    #[test]
+    fn it_should_limit_the_length_of_strings() {
+        let options = ParserOptions {
+            max_string_length: Some(3),
+            ..ParserOptions::default()
+        };
+        let tokenize = |json| {
+            Tokenizer::with_options(json, options.clone())
+                .next()
+                .unwrap()
+        };
+
+        assert!(tokenize(r#""abc""#).is_ok());
+        assert!(tokenize(r#""\n\n\n""#).is_ok());
+        assert_eq!(
+            Err(JsonError::StringTooLong(3, Position::new(0, 1, 1))),
+            tokenize(r#""abcd""#)
+        );
+        assert_eq!(
+            Err(JsonError::StringTooLong(3, Position::new(0, 1, 1))),
+            tokenize(r#""é\u00e9""#)
+        );
+    }
+
+    #[test]
+    fn it_should_limit_the_size_of_the_input() {
+        let options = ParserOptions {
+            max_input_size: Some(8),
+            ..ParserOptions::default()
+        };
+        let tokens = |json: &str| {
+            Tokenizer::from_reader_with_options(json.as_bytes(), options.clone())
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        assert!(tokens("[1, 2]  ").is_ok());
+        assert_eq!(
+            Err(JsonError::InputTooLarge(Position::new(8, 1, 9))),
+            tokens("[1, 2, 3]")
+        );
+        assert_eq!(
+            Err(JsonError::InputTooLarge(Position::new(7, 1, 8))),
+            tokens("      \"é\"")
+        );
+    }
+
+    fn json5() -> ParserOptions {
+        ParserOptions {
+            json5: true,
+            ..ParserOptions::default()
+        }
+    }
+
+    fn json5_tokens(json: &str) -> Result<Vec<Token>, JsonError> {
+        Tokenizer::with_options(json, json5())
+            .map(|token| token.map(|spanned| spanned.token))
+            .collect()
+    }
+
+    #[test]
+    fn it_should_skip_json5_comments() {
+        let json = "// line\n[ /* block\n * comment */ 1 // end\n]\u{FEFF}\u{A0}";
+
+        assert_eq!(
+            Ok(vec![Token::OpenBracket, Token::Int(1), Token::CloseBracket]),
+            json5_tokens(json)
+        );
+        assert_eq!(
+            Err(JsonError::UnterminatedComment(Position::new(2, 1, 3))),
+            json5_tokens("1 /* open")
+        );
+        assert_eq!(
+            Err(JsonError::UnexpectedCharacter('/', Position::new(2, 1, 3))),
+            json5_tokens("1 / 2")
+        );
+        assert_eq!(
+            Err(JsonError::UnexpectedCharacter('/', Position::new(0, 1, 1))),
+            Tokenizer::new("// comment").next().unwrap()
+        );
+    }
+
+    #[test]
+    fn it_should_tokenize_json5_strings_and_identifiers() {
+        assert_eq!(
+            Ok(vec![
+                Token::Str("it's \"quoted\"".to_string()),
+                Token::Str("\u{b}\0Aé".to_string()),
+                Token::Str("joined".to_string()),
+                Token::Identifier("$key_1".to_string()),
+                Token::Bool(true),
+                Token::Null,
+            ]),
+            json5_tokens("'it\\'s \"quoted\"' '\\v\\0\\x41\\é' 'join\\\ned' $key_1 true null")
+        );
+        assert_eq!(
+            Err(JsonError::ControlCharacter('\n', Position::new(2, 1, 3))),
+            json5_tokens("'a\nb'")
+        );
+        assert_eq!(
+            Err(JsonError::InvalidEscape('1', Position::new(1, 1, 2))),
+            json5_tokens("'\\1'")
+        );
+    }
+
+    #[test]
+    fn it_should_tokenize_json5_numbers() {
+        assert_eq!(
+            Ok(vec![
+                Token::Int(255),
+                Token::Int(-16),
+                Token::UInt(u64::MAX),
+                Token::Int(1),
+                Token::Float(0.5),
+                Token::Float(-5.0),
+                Token::Float(2e3),
+                Token::Float(f64::INFINITY),
+                Token::Float(f64::NEG_INFINITY),
+            ]),
+            json5_tokens("0xFF -0x10 0xFFFFFFFFFFFFFFFF +1 .5 -5. 2.E3 Infinity -Infinity")
+        );
+        assert!(matches!(
+            json5_tokens("NaN").as_deref(),
+            Ok([Token::Float(value)]) if value.is_nan()
+        ));
+        for json in ["0x", "0xG", "01", ".", "+-1", "1e", "+Infinit", "-NaNa"] {
+            assert_eq!(
+                Err(JsonError::InvalidNumber(
+                    json.to_string(),
+                    Position::new(0, 1, 1)
+                )),
+                tokenize_number(json, json5()),
+                "{} should be rejected",
+                json
+            );
+        }
+        assert_eq!(
+            Ok(Token::Float(18446744073709551616.0)),
+            tokenize_number("0x10000000000000000", json5())
+        );
+        assert!(tokenize_number("+1", ParserOptions::default()).is_err());
+    }
+
+    #[test]
+    fn it_should_apply_the_number_overflow_policy_to_hexadecimal_numbers() {
+        let big = "0x1FFFFFFFFFFFFFFFF";
+        let with_policy = |number_overflow| ParserOptions {
+            number_overflow,
+            ..json5()
+        };
+
+        assert_eq!(
+            Err(JsonError::NumberOverflow(
+                big.to_string(),
+                Position::new(0, 1, 1)
+            )),
+            tokenize_number(big, with_policy(NumberOverflow::Error))
+        );
+        assert_eq!(
+            Ok(Token::Float(36893488147419103231.0)),
+            tokenize_number(big, with_policy(NumberOverflow::Float))
+        );
+        assert_eq!(
+            Ok(Token::Decimal("36893488147419103231".to_string())),
+            tokenize_number(big, with_policy(NumberOverflow::Decimal))
+        );
+        assert_eq!(
+            Ok(Token::Decimal("-36893488147419103231".to_string())),
+            tokenize_number("-0x1FFFFFFFFFFFFFFFF", with_policy(NumberOverflow::Decimal))
+        );
+        assert_eq!(
+            Ok(Token::Int(i64::MIN)),
+            tokenize_number("-0x8000000000000000", json5())
+        );
+    }
 }
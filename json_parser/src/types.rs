@@ -1,41 +1,255 @@
 use crate::JsonType;
+use std::collections::HashMap;
 use std::ops::Index;
 
+/// A JSON number
+///
+/// Integers use `Integer` when they fit in an `i64` and `UInteger` for the
+/// positive values beyond. `Decimal` keeps the exact text of numbers too
+/// large for any of the other variants, see [`NumberOverflow`](crate::NumberOverflow).
 #[derive(Debug, PartialEq, Clone)]
 pub enum Num {
     Integer(i64),
+    UInteger(u64),
     Float(f64),
+    Decimal(String),
 }
 
 impl Num {
     pub fn serialize(&self) -> String {
         match self {
             Num::Integer(value) => value.to_string(),
+            Num::UInteger(value) => value.to_string(),
             Num::Float(value) => value.to_string(),
+            Num::Decimal(value) => value.clone(),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// A JSON object keeping its members in insertion order
+///
+/// Members are stored in a vector and their keys hashed to their index, so
+/// looking up a key takes constant time whatever the size of the object.
+/// Removing a member shifts the ones after it.
+///
+/// An object holds each key once: inserting an existing key replaces its
+/// value in place. How the parser handles an input repeating a key is set by
+/// [`DuplicateKeys`](crate::DuplicateKeys).
+#[derive(Debug, Clone, Default)]
 pub struct Object {
-    pub(crate) data: Vec<(String, JsonType)>,
+    data: Vec<(String, JsonType)>,
+    indices: HashMap<String, usize>,
 }
 
 impl Object {
+    /// Builds an object from its members, a repeated key keeps the place of
+    /// its first occurrence and the value of its last one
     pub fn new(data: Vec<(String, JsonType)>) -> Self {
-        Self { data }
+        data.into_iter().collect()
+    }
+
+    /// Returns the value of `key`, if the object has it
+    pub fn get(&self, key: &str) -> Option<&JsonType> {
+        let index = *self.indices.get(key)?;
+        Some(&self.data[index].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JsonType> {
+        let index = *self.indices.get(key)?;
+        Some(&mut self.data[index].1)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    /// Sets the value of `key`, returning its previous value
+    ///
+    /// A new key goes after the existing ones, a replaced key keeps its place.
+    pub fn insert(&mut self, key: impl Into<String>, value: JsonType) -> Option<JsonType> {
+        let key = key.into();
+        if let Some(previous) = self.get_mut(&key) {
+            return Some(std::mem::replace(previous, value));
+        }
+        self.indices.insert(key.clone(), self.data.len());
+        self.data.push((key, value));
+        None
+    }
+
+    /// Removes `key` and returns its value, the other members keep their order
+    pub fn remove(&mut self, key: &str) -> Option<JsonType> {
+        let index = self.indices.remove(key)?;
+        let (_, value) = self.data.remove(index);
+        for (key, _) in &self.data[index..] {
+            if let Some(shifted) = self.indices.get_mut(key) {
+                *shifted -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Iterates over the keys in insertion order
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.data.iter().map(|(key, _)| key.as_str())
+    }
+
+    /// Iterates over the values in insertion order
+    pub fn values(&self) -> impl Iterator<Item = &JsonType> {
+        self.data.iter().map(|(_, value)| value)
+    }
+
+    /// Iterates over the members in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &JsonType)> {
+        self.data.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// The members in insertion order
+    pub(crate) fn members(&self) -> &[(String, JsonType)] {
+        &self.data
+    }
+
+    /// Empties the object, yielding the values of its members
+    pub(crate) fn drain_values(&mut self) -> impl Iterator<Item = JsonType> + '_ {
+        self.indices.clear();
+        self.data.drain(..).map(|(_, value)| value)
+    }
+}
+
+/// Objects are equal when they hold the same members in the same order
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+    }
+}
+
+impl FromIterator<(String, JsonType)> for Object {
+    fn from_iter<I: IntoIterator<Item = (String, JsonType)>>(members: I) -> Self {
+        let mut object = Object::default();
+        object.extend(members);
+        object
+    }
+}
+
+impl Extend<(String, JsonType)> for Object {
+    fn extend<I: IntoIterator<Item = (String, JsonType)>>(&mut self, members: I) {
+        for (key, value) in members {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl IntoIterator for Object {
+    type Item = (String, JsonType);
+    type IntoIter = std::vec::IntoIter<(String, JsonType)>;
+
+    /// Consumes the object, yielding its members in insertion order
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
     }
 }
 
 impl Index<&str> for Object {
     type Output = JsonType;
 
-    fn index<'a, 'b>(&'a self, index: &'b str) -> &'a Self::Output {
-        for (key, value) in &self.data {
-            if key == index {
-                return &value;
-            }
+    fn index<'a>(&'a self, index: &str) -> &'a Self::Output {
+        self.get(index)
+            .expect("Error: trying to dereference Json object with unknown key")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object() -> Object {
+        Object::new(vec![
+            ("b".to_string(), JsonType::Bool(true)),
+            ("a".to_string(), JsonType::Null),
+        ])
+    }
+
+    #[test]
+    fn it_should_look_up_keys() {
+        let object = object();
+
+        assert_eq!(Some(&JsonType::Null), object.get("a"));
+        assert_eq!(None, object.get("c"));
+        assert!(object.contains_key("b"));
+        assert!(!object.contains_key("c"));
+        assert_eq!(2, object.len());
+        assert!(!object.is_empty());
+        assert!(Object::new(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn it_should_iterate_in_insertion_order() {
+        let object = object();
+
+        assert_eq!(vec!["b", "a"], object.keys().collect::<Vec<_>>());
+        assert_eq!(
+            vec![&JsonType::Bool(true), &JsonType::Null],
+            object.values().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![("b", &JsonType::Bool(true)), ("a", &JsonType::Null)],
+            object.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn it_should_insert_and_remove_keys() {
+        let mut object = object();
+
+        assert_eq!(None, object.insert("c", JsonType::Null));
+        assert_eq!(
+            Some(JsonType::Bool(true)),
+            object.insert("b", JsonType::Bool(false))
+        );
+        assert_eq!(vec!["b", "a", "c"], object.keys().collect::<Vec<_>>());
+
+        assert_eq!(Some(JsonType::Null), object.remove("a"));
+        assert_eq!(None, object.remove("a"));
+        assert_eq!(vec!["b", "c"], object.keys().collect::<Vec<_>>());
+        *object.get_mut("c").unwrap() = JsonType::Bool(true);
+        assert_eq!(JsonType::Bool(true), object["c"]);
+    }
+
+    #[test]
+    fn it_should_keep_one_member_per_key() {
+        let object = Object::new(vec![
+            ("a".to_string(), JsonType::Null),
+            ("b".to_string(), JsonType::Null),
+            ("a".to_string(), JsonType::Bool(true)),
+        ]);
+
+        assert_eq!(2, object.len());
+        assert_eq!(vec!["a", "b"], object.keys().collect::<Vec<_>>());
+        assert_eq!(JsonType::Bool(true), object["a"]);
+    }
+
+    #[test]
+    fn it_should_find_keys_after_removing_members() {
+        let mut object: Object = (0..100)
+            .map(|i| (i.to_string(), JsonType::Num(Num::Integer(i))))
+            .collect();
+
+        for i in (0..100).step_by(3) {
+            object.remove(&i.to_string());
+        }
+
+        assert_eq!(66, object.len());
+        for (key, value) in object.iter() {
+            assert_eq!(Some(value), object.get(key));
         }
-        panic!("Error: trying to dereference Json object with unknown key");
+        assert_eq!(None, object.get("99"));
+        assert_eq!(Some(&JsonType::Num(Num::Integer(98))), object.get("98"));
     }
 }
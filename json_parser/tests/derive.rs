@@ -0,0 +1,582 @@
+use json_parser::error::JsonError;
+use json_parser::types::Num;
+use json_parser::{
+    Deserialize, JsonType, Serialize, from_string, to_string, to_string_pretty, to_value,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Person {
+    name: JsonType,
+    nickname: Option<JsonType>,
+    address: Address,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Address {
+    city: JsonType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Point(JsonType, JsonType);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Wrapper(JsonType);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Marker;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Generic<T> {
+    value: T,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Empty,
+    Circle(JsonType),
+    Line(Point, Point),
+    Rectangle { width: JsonType, height: JsonType },
+}
+
+fn int(value: i64) -> JsonType {
+    JsonType::Num(Num::Integer(value))
+}
+
+fn string(value: &str) -> JsonType {
+    JsonType::Str(value.to_string())
+}
+
+#[test]
+fn it_should_derive_named_struct() {
+    let person = Person {
+        name: string("Alice"),
+        nickname: None,
+        address: Address {
+            city: string("Paris"),
+        },
+    };
+
+    let serialized = to_string(person.clone()).unwrap();
+    assert_eq!(
+        r#"{"name":"Alice","nickname":null,"address":{"city":"Paris"}}"#,
+        serialized
+    );
+    assert_eq!(person, from_string::<Person>(&serialized).unwrap());
+}
+
+#[test]
+fn it_should_treat_missing_optional_field_as_none() {
+    let person: Person = from_string(r#"{"name": "Bob", "address": {"city": "Lyon"}}"#).unwrap();
+
+    assert_eq!(None, person.nickname);
+}
+
+#[test]
+fn it_should_ignore_unknown_fields() {
+    let address: Address = from_string(r#"{"zip": 75000, "city": "Paris"}"#).unwrap();
+
+    assert_eq!(string("Paris"), address.city);
+}
+
+#[test]
+fn it_should_report_missing_field() {
+    let result = from_string::<Person>(r#"{"name": "Bob"}"#);
+
+    assert_eq!(
+        Err(JsonError::DeserializationError(
+            "missing field `address` in Person".to_string()
+        )),
+        result
+    );
+}
+
+#[test]
+fn it_should_report_invalid_type() {
+    let result = from_string::<Address>("[1]");
+
+    assert_eq!(
+        Err(JsonError::DeserializationError(
+            "expected an object for Address, got an array".to_string()
+        )),
+        result
+    );
+}
+
+#[test]
+fn it_should_derive_tuple_newtype_and_unit_structs() {
+    assert_eq!("[1,2]", to_string(Point(int(1), int(2))).unwrap());
+    assert_eq!(Point(int(1), int(2)), from_string("[1, 2]").unwrap());
+
+    assert_eq!("true", to_string(Wrapper(JsonType::Bool(true))).unwrap());
+    assert_eq!(Wrapper(JsonType::Bool(true)), from_string("true").unwrap());
+
+    assert_eq!("null", to_string(Marker).unwrap());
+    assert_eq!(Marker, from_string("null").unwrap());
+}
+
+#[test]
+fn it_should_report_invalid_tuple_length() {
+    let result = from_string::<Point>("[1, 2, 3]");
+
+    assert_eq!(
+        Err(JsonError::DeserializationError(
+            "expected an array of 2 elements for Point, got 3".to_string()
+        )),
+        result
+    );
+}
+
+#[test]
+fn it_should_derive_generic_struct() {
+    let generic = Generic {
+        value: Some(int(3)),
+    };
+
+    assert_eq!(r#"{"value":3}"#, to_string(generic.clone()).unwrap());
+    assert_eq!(generic, from_string(r#"{"value":3}"#).unwrap());
+}
+
+#[test]
+fn it_should_derive_externally_tagged_enum() {
+    let cases = [
+        (Shape::Empty, r#""Empty""#),
+        (Shape::Circle(int(2)), r#"{"Circle":2}"#),
+        (
+            Shape::Line(Point(int(0), int(0)), Point(int(1), int(1))),
+            r#"{"Line":[[0,0],[1,1]]}"#,
+        ),
+        (
+            Shape::Rectangle {
+                width: int(3),
+                height: int(4),
+            },
+            r#"{"Rectangle":{"width":3,"height":4}}"#,
+        ),
+    ];
+
+    for (shape, json) in cases {
+        assert_eq!(json, to_string(shape.clone()).unwrap());
+        assert_eq!(shape, from_string(json).unwrap());
+    }
+}
+
+#[test]
+fn it_should_report_unknown_variant() {
+    let result = from_string::<Shape>(r#"{"Triangle": 3}"#);
+
+    assert_eq!(
+        Err(JsonError::DeserializationError(
+            "unknown variant `Triangle` for Shape".to_string()
+        )),
+        result
+    );
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Never {}
+
+#[test]
+fn it_should_derive_empty_enum() {
+    let result = from_string::<Never>(r#""Any""#);
+
+    assert_eq!(
+        JsonError::DeserializationError("unknown variant `Any` for Never".to_string()),
+        result.unwrap_err()
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[json(rename_all = "camelCase")]
+struct Account {
+    user_name: JsonType,
+    #[json(rename = "e-mail")]
+    email_address: JsonType,
+    #[json(default)]
+    display_name: Option<JsonType>,
+    #[json(default = "default_role")]
+    user_role: JsonType,
+    #[json(skip)]
+    session: Option<JsonType>,
+    #[json(skip_serializing_if = "Option::is_none")]
+    last_login: Option<JsonType>,
+}
+
+fn default_role() -> JsonType {
+    string("member")
+}
+
+#[test]
+fn it_should_apply_field_attributes() {
+    let account = Account {
+        user_name: string("alice"),
+        email_address: string("alice@example.com"),
+        display_name: None,
+        user_role: string("admin"),
+        session: Some(string("secret")),
+        last_login: None,
+    };
+
+    assert_eq!(
+        r#"{"userName":"alice","e-mail":"alice@example.com","displayName":null,"userRole":"admin"}"#,
+        to_string(account).unwrap()
+    );
+
+    let account: Account = from_string(
+        r#"{"userName":"bob","e-mail":"bob@example.com","session":"x","lastLogin":"today"}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        Account {
+            user_name: string("bob"),
+            email_address: string("bob@example.com"),
+            display_name: None,
+            user_role: string("member"),
+            session: None,
+            last_login: Some(string("today")),
+        },
+        account
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[json(deny_unknown_fields)]
+struct Strict {
+    id: JsonType,
+}
+
+#[test]
+fn it_should_deny_unknown_fields() {
+    let result = from_string::<Strict>(r#"{"id": 1, "extra": 2}"#);
+
+    assert_eq!(
+        Err(JsonError::DeserializationError(
+            "unknown field `extra` in Strict".to_string()
+        )),
+        result
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Page {
+    title: JsonType,
+    #[json(flatten)]
+    metadata: Metadata,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Metadata {
+    author: JsonType,
+    version: JsonType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Document {
+    id: JsonType,
+    #[json(flatten)]
+    extra: JsonType,
+}
+
+#[test]
+fn it_should_flatten_struct_field() {
+    let json = r#"{"title":"Home","author":"Alice","version":2}"#;
+    let page: Page = from_string(json).unwrap();
+
+    assert_eq!(
+        Metadata {
+            author: string("Alice"),
+            version: int(2),
+        },
+        page.metadata
+    );
+    assert_eq!(json, to_string(page).unwrap());
+}
+
+#[test]
+fn it_should_collect_unknown_members_into_flatten_field() {
+    let json = r#"{"id":1,"draft":true,"tags":[]}"#;
+    let document: Document = from_string(json).unwrap();
+
+    assert_eq!(JsonType::Bool(true), document.extra["draft"]);
+    assert_eq!(JsonType::Array(vec![]), document.extra["tags"]);
+    assert_eq!(json, to_string(document).unwrap());
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[json(rename_all = "snake_case")]
+enum Event {
+    UserCreated {
+        user_id: JsonType,
+    },
+    #[json(rename = "deleted", rename_all = "camelCase")]
+    UserDeleted {
+        user_id: JsonType,
+    },
+    LoggedOut,
+}
+
+#[test]
+fn it_should_rename_variants() {
+    let cases = [
+        (
+            Event::UserCreated { user_id: int(1) },
+            r#"{"user_created":{"user_id":1}}"#,
+        ),
+        (
+            Event::UserDeleted { user_id: int(1) },
+            r#"{"deleted":{"userId":1}}"#,
+        ),
+        (Event::LoggedOut, r#""logged_out""#),
+    ];
+
+    for (event, json) in cases {
+        assert_eq!(json, to_string(event.clone()).unwrap());
+        assert_eq!(event, from_string(json).unwrap());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[json(tag = "type")]
+enum Message {
+    Ping,
+    Text { body: JsonType },
+    Wrapped(Address),
+}
+
+#[test]
+fn it_should_derive_internally_tagged_enum() {
+    let cases = [
+        (Message::Ping, r#"{"type":"Ping"}"#),
+        (
+            Message::Text { body: string("hi") },
+            r#"{"type":"Text","body":"hi"}"#,
+        ),
+        (
+            Message::Wrapped(Address {
+                city: string("Paris"),
+            }),
+            r#"{"type":"Wrapped","city":"Paris"}"#,
+        ),
+    ];
+
+    for (message, json) in cases {
+        assert_eq!(json, to_string(message.clone()).unwrap());
+        assert_eq!(message, from_string(json).unwrap());
+    }
+    assert_eq!(
+        Message::Text { body: string("hi") },
+        from_string(r#"{"body":"hi","type":"Text"}"#).unwrap()
+    );
+}
+
+#[test]
+fn it_should_report_missing_tag() {
+    let result = from_string::<Message>(r#"{"body":"hi"}"#);
+
+    assert_eq!(
+        Err(JsonError::DeserializationError(
+            "missing tag `type` in Message".to_string()
+        )),
+        result
+    );
+}
+
+#[derive(Debug, Serialize)]
+#[json(tag = "type")]
+enum Counter {
+    Count(u32),
+}
+
+#[test]
+fn it_should_report_internally_tagged_content_that_is_not_an_object() {
+    let error = || {
+        JsonError::SerializationError(
+            "cannot serialize a number as the content of internally tagged variant `Count`"
+                .to_string(),
+        )
+    };
+
+    assert_eq!(Err(error()), to_string(Counter::Count(3)));
+    assert_eq!(Err(error()), to_string_pretty(Counter::Count(3)));
+    assert_eq!(Err(error()), to_value(&Counter::Count(3)));
+    assert_eq!(Err(error()), to_string(Kind::Counter(Counter::Count(3))));
+}
+
+#[derive(Debug, Serialize)]
+#[json(tag = "kind")]
+enum Kind {
+    Counter(Counter),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[json(tag = "t", content = "c")]
+enum Command {
+    Stop,
+    Move(Point),
+    Say { text: JsonType },
+}
+
+#[test]
+fn it_should_derive_adjacently_tagged_enum() {
+    let cases = [
+        (Command::Stop, r#"{"t":"Stop"}"#),
+        (
+            Command::Move(Point(int(1), int(2))),
+            r#"{"t":"Move","c":[1,2]}"#,
+        ),
+        (
+            Command::Say {
+                text: string("hello"),
+            },
+            r#"{"t":"Say","c":{"text":"hello"}}"#,
+        ),
+    ];
+
+    for (command, json) in cases {
+        assert_eq!(json, to_string(command.clone()).unwrap());
+        assert_eq!(command, from_string(json).unwrap());
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[json(untagged)]
+enum Value {
+    Nothing,
+    Pair(JsonType, JsonType),
+    Named { origin: Point, target: Point },
+    Other(Strict),
+}
+
+#[test]
+fn it_should_derive_untagged_enum() {
+    let cases = [
+        (Value::Nothing, "null"),
+        (Value::Pair(int(1), int(2)), "[1,2]"),
+        (
+            Value::Named {
+                origin: Point(int(0), int(0)),
+                target: Point(int(1), int(1)),
+            },
+            r#"{"origin":[0,0],"target":[1,1]}"#,
+        ),
+        (Value::Other(Strict { id: int(1) }), r#"{"id":1}"#),
+    ];
+
+    for (value, json) in cases {
+        assert_eq!(json, to_string(value.clone()).unwrap());
+        assert_eq!(value, from_string(json).unwrap());
+    }
+}
+
+#[test]
+fn it_should_report_closest_untagged_variant() {
+    let result = from_string::<Value>(r#"{"origin":[0,0]}"#);
+
+    assert_eq!(
+        Err(JsonError::DeserializationError(
+            "data did not match any variant of untagged enum Value, closest variant `Named` \
+             failed: missing field `target` in Value::Named"
+                .to_string()
+        )),
+        result
+    );
+}
+
+#[derive(Debug, PartialEq, json_parser::de::FromDeserializer)]
+#[json(rename_all = "camelCase")]
+struct Borrowed<'a> {
+    user_name: &'a str,
+    display_name: std::borrow::Cow<'a, str>,
+    age: u8,
+    #[json(default)]
+    tags: Vec<String>,
+    nickname: Option<&'a str>,
+    #[json(skip)]
+    cache: Option<u8>,
+}
+
+#[test]
+fn it_should_derive_from_deserializer_borrowing_input() {
+    let input =
+        r#"{"userName": "alice", "displayName": "Alice!", "age": 30, "extra": [1, {"a": 2}]}"#;
+    let borrowed: Borrowed = json_parser::de::from_str(input).unwrap();
+
+    assert_eq!(
+        Borrowed {
+            user_name: "alice",
+            display_name: "Alice!".into(),
+            age: 30,
+            tags: vec![],
+            nickname: None,
+            cache: None,
+        },
+        borrowed
+    );
+    assert!(std::ptr::eq(&input[14..19], borrowed.user_name));
+}
+
+#[test]
+fn it_should_report_missing_visited_field() {
+    let result = json_parser::de::from_str::<Borrowed>(r#"{"userName": "a", "displayName": "b"}"#);
+
+    assert_eq!(
+        Err(JsonError::DeserializationError(
+            "missing field `age` in Borrowed".to_string()
+        )),
+        result
+    );
+}
+
+#[derive(Debug, PartialEq, json_parser::de::FromDeserializer)]
+#[json(deny_unknown_fields)]
+struct StrictVisited<T> {
+    id: T,
+}
+
+#[test]
+fn it_should_derive_generic_from_deserializer() {
+    assert_eq!(
+        Ok(StrictVisited { id: 7u64 }),
+        json_parser::de::from_str(r#"{"id": 7}"#)
+    );
+    assert_eq!(
+        Err(JsonError::DeserializationError(
+            "unknown field `other` in StrictVisited".to_string()
+        )),
+        json_parser::de::from_str::<StrictVisited<u64>>(r#"{"id": 7, "other": 1}"#)
+    );
+    assert_eq!(
+        Err(JsonError::DeserializationError(
+            "expected an object for StrictVisited, got an array".to_string()
+        )),
+        json_parser::de::from_str::<StrictVisited<u64>>("[]")
+    );
+}
+
+fn assert_streams_as_tree<T: Serialize>(value: T) {
+    let tree = to_string(value.serialize()).unwrap();
+
+    assert_eq!(tree, to_string(value).unwrap());
+}
+
+#[test]
+fn it_should_stream_the_same_json_as_the_tree() {
+    assert_streams_as_tree(Page {
+        title: string("Home"),
+        metadata: Metadata {
+            author: string("Alice"),
+            version: int(2),
+        },
+    });
+    assert_streams_as_tree(Message::Wrapped(Address {
+        city: string("Paris"),
+    }));
+    assert_streams_as_tree(Command::Move(Point(int(1), int(2))));
+    assert_streams_as_tree(Value::Named {
+        origin: Point(int(0), int(0)),
+        target: Point(int(1), int(1)),
+    });
+    assert_streams_as_tree(Shape::Rectangle {
+        width: int(3),
+        height: int(4),
+    });
+}